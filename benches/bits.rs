@@ -0,0 +1,147 @@
+#![allow(deprecated)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hips_lib::bit_ops::{BitBuffer, BitOps, BitOrder, BitReader, BitWriter};
+use hips_lib::carrier::{embed, extract};
+use hips_lib::stego::{Ecc, Stego};
+
+const PAYLOAD_LEN: usize = 64 * 1024;
+
+/// Number of allocations made since the start of the benchmarks.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator counting every allocation and reallocation.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs the routine once and prints the number of allocations it made next to the timings.
+fn report_allocations<T>(name: &str, mut routine: impl FnMut() -> T) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(routine());
+    println!(
+        "{name}: {} allocations",
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    );
+}
+
+fn payload() -> Vec<u8> {
+    (0..PAYLOAD_LEN).map(|n| (n * 7919 % 251) as u8).collect()
+}
+
+/// Expanding bytes to bits: one `Vec<bool>` per byte against reading the packed bytes.
+fn expand_bits(c: &mut Criterion) {
+    let bytes = payload();
+    let bit_buffer = || {
+        black_box(&bytes)
+            .iter()
+            .flat_map(|byte| byte.to_bit_buffer())
+            .filter(|bit| *bit)
+            .count()
+    };
+    let bit_reader = || {
+        BitReader::new(black_box(&bytes), BitOrder::LsbFirst)
+            .filter(|bit| *bit)
+            .count()
+    };
+
+    report_allocations("expand bits/to_bit_buffer", bit_buffer);
+    report_allocations("expand bits/BitReader", bit_reader);
+
+    let mut group = c.benchmark_group("expand bits");
+    group.bench_function("to_bit_buffer", |b| b.iter(bit_buffer));
+    group.bench_function("BitReader", |b| b.iter(bit_reader));
+    group.finish();
+}
+
+/// Collecting bits to bytes: a `Vec<bool>` folded with `set_bit` against writing packed bytes.
+fn collect_bits(c: &mut Criterion) {
+    let bits: Vec<bool> = BitReader::new(&payload(), BitOrder::LsbFirst).collect();
+    let set_bit = || {
+        let buffer: Vec<bool> = black_box(&bits).to_vec();
+        buffer
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (idx, bit)| byte.set_bit(idx as u32, *bit))
+            })
+            .collect::<Vec<u8>>()
+    };
+    let bit_writer = || {
+        let mut writer = BitWriter::with_capacity(bits.len(), BitOrder::LsbFirst);
+        black_box(&bits)
+            .iter()
+            .for_each(|bit| writer.write_bit(*bit));
+        writer.into_bytes()
+    };
+
+    report_allocations("collect bits/set_bit", set_bit);
+    report_allocations("collect bits/BitWriter", bit_writer);
+
+    let mut group = c.benchmark_group("collect bits");
+    group.bench_function("set_bit", |b| b.iter(set_bit));
+    group.bench_function("BitWriter", |b| b.iter(bit_writer));
+    group.finish();
+}
+
+/// Hiding and retrieving a payload in a carrier.
+fn embed_extract(c: &mut Criterion) {
+    let bytes = payload();
+    let mut samples = vec![0u8; PAYLOAD_LEN * 16];
+    let stego = Stego::builder().ecc(Ecc::Hamming).build().unwrap();
+
+    report_allocations("embed", || {
+        embed(samples.as_mut_slice(), black_box(&bytes)).unwrap()
+    });
+    report_allocations("extract", || extract(black_box(samples.as_slice())));
+    report_allocations("stego hide hamming", || {
+        stego
+            .hide(samples.as_mut_slice(), black_box(&bytes))
+            .unwrap()
+    });
+    report_allocations("stego extract hamming", || {
+        stego.extract(black_box(samples.as_slice())).unwrap()
+    });
+
+    c.bench_function("embed", |b| {
+        b.iter(|| embed(samples.as_mut_slice(), black_box(&bytes)).unwrap())
+    });
+    c.bench_function("extract", |b| {
+        b.iter(|| extract(black_box(samples.as_slice())))
+    });
+    c.bench_function("stego hide hamming", |b| {
+        b.iter(|| {
+            stego
+                .hide(samples.as_mut_slice(), black_box(&bytes))
+                .unwrap()
+        })
+    });
+    c.bench_function("stego extract hamming", |b| {
+        b.iter(|| stego.extract(black_box(samples.as_slice())).unwrap())
+    });
+}
+
+criterion_group!(benches, expand_bits, collect_bits, embed_extract);
+criterion_main!(benches);
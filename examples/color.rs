@@ -1,12 +1,15 @@
-use hips_lib::{color::Color, hips::{hide_secret_col, find_secret_col}};
+use hips_lib::{
+    color::Color,
+    hips::{find_secret_col, hide_secret_col},
+    secret::SecretString,
+};
 
 extern crate hips_lib;
 
-
 fn main() {
     // Create a vector of four hundred pixels.
     let mut pixels = vec![Color::new(); 400];
-    let password = String::from("password");
+    let password = SecretString::from("password");
 
     // Your secret text
     let secret = String::from("Lorem ipsum dolor sit amet, consectetur adipisici elit, sed eiusmod tempor incidunt ut labore et dolore magna aliqua.");
@@ -18,5 +21,5 @@ fn main() {
     let result = find_secret_col(&pixels, Some(password));
 
     // Test the results
-    assert_eq!(Some(secret), result);
-}
\ No newline at end of file
+    assert_eq!(Some(secret.as_str()), result.as_deref());
+}
@@ -0,0 +1,697 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use crate::carrier::{capacity, embed, extract, Carrier};
+use crate::color::Color;
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::palette::{embed_indexed, extract_indexed, indexed_capacity, pack_indices, pad_palette, png_bit_depth, unpack_indices, IndexedImage};
+use crate::secret::SecretString;
+
+/// Number of bytes in front of every frame segment: the segment index and the segment count, both as little endian `u16`.
+pub const SEGMENT_HEADER_LEN: usize = 4;
+
+/// File format of an animation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+/// Describes how the frame area is treated before the next frame is rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Disposal {
+    /// No disposal specified (GIF only), usually treated like `Keep`.
+    Unspecified,
+    /// The frame is left in place.
+    Keep,
+    /// The frame area is cleared to the background.
+    Background,
+    /// The frame area is restored to its state before the frame was rendered.
+    Previous,
+}
+
+/// Pixel data of a single animation frame.
+#[derive(Clone)]
+pub enum FrameData {
+    /// Palette indices, data is hidden in the luminance sorted palette like in indexed images.
+    Indexed(IndexedImage),
+    /// True color pixels, data is hidden in the R, G and B values.
+    Color { width: u32, height: u32, pixels: Vec<Color> },
+}
+
+/// A single frame of an animation together with its timing and disposal.
+#[derive(Clone)]
+pub struct Frame {
+    pub data: FrameData,
+    /// Horizontal position of the frame on the canvas.
+    pub left: u32,
+    /// Vertical position of the frame on the canvas.
+    pub top: u32,
+    /// Display duration in seconds as fraction of numerator and denominator.
+    pub delay: (u16, u16),
+    pub disposal: Disposal,
+    /// Whether the frame is alpha blended over the canvas (APNG only, GIF frames are always blended).
+    pub blend: bool,
+}
+
+/// An animated GIF or APNG image.
+#[derive(Clone)]
+pub struct Animation {
+    pub format: AnimationFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Number of times the animation is played, zero for an endless loop.
+    pub plays: u32,
+    /// Global palette of GIF animations as flat RGB triples.
+    pub global_palette: Option<Vec<u8>>,
+    pub frames: Vec<Frame>,
+}
+
+impl Frame {
+    /// Returns the width and height of the frame.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match &self.data {
+            FrameData::Indexed(img) => (img.width, img.height),
+            FrameData::Color { width, height, .. } => (*width, *height),
+        }
+    }
+
+    /// Returns the number of bytes which can be hidden in the frame, including the segment header.
+    pub fn capacity(&self) -> usize {
+        match &self.data {
+            FrameData::Indexed(img) => indexed_capacity(img),
+            FrameData::Color { pixels, .. } => capacity(pixels.sample_count()),
+        }
+    }
+
+    /// Returns the delay in hundredths of a second as used by GIF.
+    fn delay_centis(&self) -> u16 {
+        let (numerator, denominator) = self.delay;
+        let denominator = if denominator == 0 { 100 } else { denominator as u32 };
+        (numerator as u32 * 100 / denominator).min(u16::MAX as u32) as u16
+    }
+
+    fn embed(&mut self, bytes: &[u8]) -> Result<(), String> {
+        match &mut self.data {
+            FrameData::Indexed(img) => embed_indexed(img, bytes),
+            FrameData::Color { pixels, .. } => embed(pixels.as_mut_slice(), bytes).map(|_| ()),
+        }
+    }
+
+    fn extract(&self) -> Vec<u8> {
+        match &self.data {
+            FrameData::Indexed(img) => extract_indexed(img),
+            FrameData::Color { pixels, .. } => extract(pixels.as_slice()),
+        }
+    }
+}
+
+impl Animation {
+    /// Tries to load an animated GIF or APNG image. The format is detected from the file contents.
+    /// Still images are loaded as animation with a single frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `img_path` - Path to the image file.
+    ///
+    pub fn open(img_path: &str) -> Result<Animation, String> {
+        let mut file = File::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+
+        let mut signature = [0u8; 4];
+        file.read_exact(&mut signature).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+        let file = File::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+
+        match &signature {
+            b"\x89PNG" => Animation::read_apng(BufReader::new(file)),
+            b"GIF8" => Animation::read_gif(BufReader::new(file)),
+            _ => Err(format!("The image '{img_path}' is neither a PNG nor a GIF image.")),
+        }
+    }
+
+    /// Tries to save the animation in the format it was loaded from.
+    ///
+    /// # Arguments
+    ///
+    /// * `img_path` - Path of the target image file.
+    ///
+    pub fn save(&self, img_path: &str) -> Result<(), String> {
+        let file = File::create(img_path).map_err(|_| format!("Failed creating output image '{img_path}'"))?;
+
+        match self.format {
+            AnimationFormat::Gif => self.write_gif(BufWriter::new(file)),
+            AnimationFormat::Apng => self.write_apng(BufWriter::new(file)),
+        }
+    }
+
+    /// Tries to decode all frames of a GIF image without expanding their palettes.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the encoded GIF image.
+    ///
+    pub fn read_gif<R: Read>(reader: R) -> Result<Animation, String> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(reader).map_err(|err| format!("Failed decoding GIF image: {err}"))?;
+
+        let global_palette = decoder.global_palette().map(|p| p.to_vec());
+        let plays = match decoder.repeat() {
+            gif::Repeat::Infinite => 0,
+            gif::Repeat::Finite(n) => n as u32 + 1,
+        };
+
+        let mut frames = vec![];
+        while let Some(frame) = decoder.read_next_frame().map_err(|err| format!("Failed decoding GIF image: {err}"))? {
+            frames.push(Frame {
+                data: FrameData::Indexed(IndexedImage::from_gif_frame(frame, global_palette.as_deref())),
+                left: frame.left as u32,
+                top: frame.top as u32,
+                delay: (frame.delay, 100),
+                disposal: match frame.dispose {
+                    gif::DisposalMethod::Any => Disposal::Unspecified,
+                    gif::DisposalMethod::Keep => Disposal::Keep,
+                    gif::DisposalMethod::Background => Disposal::Background,
+                    gif::DisposalMethod::Previous => Disposal::Previous,
+                },
+                blend: true,
+            });
+        }
+
+        if frames.is_empty() {
+            return Err(String::from("The GIF image does not contain any frames."));
+        }
+
+        Ok(Animation {
+            format: AnimationFormat::Gif,
+            width: decoder.width() as u32,
+            height: decoder.height() as u32,
+            plays,
+            global_palette,
+            frames,
+        })
+    }
+
+    /// Tries to encode the animation as GIF image. All frames have to be indexed.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Target of the encoded GIF image.
+    ///
+    pub fn write_gif<W: Write>(&self, writer: W) -> Result<(), String> {
+        if self.width > u16::MAX as u32 || self.height > u16::MAX as u32 {
+            return Err(String::from("The animation exceeds the limits of the GIF format."));
+        }
+
+        let global_palette = self.global_palette.clone().unwrap_or_default();
+        let mut encoder = gif::Encoder::new(writer, self.width as u16, self.height as u16, &global_palette).map_err(|err| format!("Failed encoding GIF image: {err}"))?;
+        if self.plays != 1 {
+            let repeat = if self.plays == 0 {
+                gif::Repeat::Infinite
+            } else {
+                gif::Repeat::Finite((self.plays - 1).min(u16::MAX as u32) as u16)
+            };
+            encoder.set_repeat(repeat).map_err(|err| format!("Failed encoding GIF image: {err}"))?;
+        }
+
+        for frame in &self.frames {
+            let img = match &frame.data {
+                FrameData::Indexed(img) if img.palette.len() <= 256 => img,
+                _ => return Err(String::from("Only indexed frames can be encoded as GIF image.")),
+            };
+
+            let mut gif_frame = img.to_gif_frame();
+            gif_frame.left = frame.left as u16;
+            gif_frame.top = frame.top as u16;
+            gif_frame.delay = frame.delay_centis();
+            gif_frame.dispose = match frame.disposal {
+                Disposal::Unspecified => gif::DisposalMethod::Any,
+                Disposal::Keep => gif::DisposalMethod::Keep,
+                Disposal::Background => gif::DisposalMethod::Background,
+                Disposal::Previous => gif::DisposalMethod::Previous,
+            };
+            if img.gif_palette() != global_palette {
+                gif_frame.palette = Some(img.gif_palette());
+            }
+
+            encoder.write_frame(&gif_frame).map_err(|err| format!("Failed encoding GIF image: {err}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Tries to decode all frames of an APNG image. Indexed and 8 bit RGB(A) images are supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the encoded APNG image.
+    ///
+    pub fn read_apng<R: Read>(reader: R) -> Result<Animation, String> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let mut reader = decoder.read_info().map_err(|err| format!("Failed decoding PNG image: {err}"))?;
+
+        let info = reader.info();
+        let (width, height, color_type, bit_depth) = (info.width, info.height, info.color_type, info.bit_depth);
+        if bit_depth != png::BitDepth::Eight && color_type != png::ColorType::Indexed {
+            return Err(String::from("Only 8 bit PNG images are supported."));
+        }
+
+        let trns = info.trns.as_ref().map(|t| t.to_vec()).unwrap_or_default();
+        let palette: Vec<Color> = match (&info.palette, color_type) {
+            (Some(plte), png::ColorType::Indexed) => plte
+                .chunks(3)
+                .enumerate()
+                .map(|(idx, rgb)| Color::from_rgba(rgb[0], rgb[1], rgb[2], *trns.get(idx).unwrap_or(&255)))
+                .collect(),
+            (_, png::ColorType::Rgb) | (_, png::ColorType::Rgba) => vec![],
+            _ => return Err(String::from("Only indexed, RGB and RGBA PNG images are supported.")),
+        };
+
+        let (num_frames, plays) = match &info.animation_control {
+            Some(_) if info.frame_control.is_none() => return Err(String::from("APNG images with a separate default image are not supported.")),
+            Some(actl) => (actl.num_frames, actl.num_plays),
+            None => (1, 0),
+        };
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut frames = vec![];
+        for _ in 0..num_frames {
+            let output = reader.next_frame(&mut buffer).map_err(|err| format!("Failed decoding PNG image: {err}"))?;
+            let (w, h) = (output.width, output.height);
+            let data = &buffer[..output.line_size * h as usize];
+
+            let data = match color_type {
+                png::ColorType::Indexed => FrameData::Indexed(IndexedImage {
+                    width: w,
+                    height: h,
+                    palette: palette.clone(),
+                    indices: unpack_indices(data, output.line_size, w as usize, h as usize, bit_depth as usize),
+                }),
+                png::ColorType::Rgb => FrameData::Color {
+                    width: w,
+                    height: h,
+                    pixels: data.chunks(3).map(|c| Color::from_rgb(c[0], c[1], c[2])).collect(),
+                },
+                _ => FrameData::Color {
+                    width: w,
+                    height: h,
+                    pixels: data.chunks(4).map(|c| Color::from_rgba(c[0], c[1], c[2], c[3])).collect(),
+                },
+            };
+
+            let frame = match &reader.info().frame_control {
+                Some(fctl) => Frame {
+                    data,
+                    left: fctl.x_offset,
+                    top: fctl.y_offset,
+                    delay: (fctl.delay_num, fctl.delay_den),
+                    disposal: match fctl.dispose_op {
+                        png::DisposeOp::None => Disposal::Keep,
+                        png::DisposeOp::Background => Disposal::Background,
+                        png::DisposeOp::Previous => Disposal::Previous,
+                    },
+                    blend: fctl.blend_op == png::BlendOp::Over,
+                },
+                None => Frame {
+                    data,
+                    left: 0,
+                    top: 0,
+                    delay: (0, 100),
+                    disposal: Disposal::Keep,
+                    blend: false,
+                },
+            };
+            frames.push(frame);
+        }
+
+        Ok(Animation {
+            format: AnimationFormat::Apng,
+            width,
+            height,
+            plays,
+            global_palette: None,
+            frames,
+        })
+    }
+
+    /// Tries to encode the animation as APNG image. A single frame is encoded as regular PNG image.
+    /// Either all frames are indexed using the same palette or all frames are true color.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Target of the encoded APNG image.
+    ///
+    pub fn write_apng<W: Write>(&self, writer: W) -> Result<(), String> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+
+        let palette = match self.frames.first().map(|f| &f.data) {
+            Some(FrameData::Indexed(img)) => Some(&img.palette),
+            Some(FrameData::Color { .. }) => None,
+            None => return Err(String::from("The animation does not contain any frames.")),
+        };
+
+        let alpha = self.frames.iter().any(|f| match &f.data {
+            FrameData::Color { pixels, .. } => pixels.iter().any(|p| p.a != 255),
+            FrameData::Indexed(_) => false,
+        });
+
+        let bit_depth = match palette {
+            Some(palette) => {
+                let bit_depth = png_bit_depth(palette.len());
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(bit_depth);
+                encoder.set_palette(palette.iter().flat_map(|c| [c.r, c.g, c.b]).collect::<Vec<u8>>());
+                if palette.iter().any(|c| c.a != 255) {
+                    encoder.set_trns(palette.iter().map(|c| c.a).collect::<Vec<u8>>());
+                }
+                bit_depth
+            }
+            None => {
+                encoder.set_color(if alpha { png::ColorType::Rgba } else { png::ColorType::Rgb });
+                encoder.set_depth(png::BitDepth::Eight);
+                png::BitDepth::Eight
+            }
+        };
+
+        let animated = self.frames.len() > 1;
+        if animated {
+            encoder.set_animated(self.frames.len() as u32, self.plays).map_err(|err| format!("Failed encoding PNG image: {err}"))?;
+        }
+
+        let mut writer = encoder.write_header().map_err(|err| format!("Failed encoding PNG image: {err}"))?;
+        for frame in &self.frames {
+            let data = match (&frame.data, palette) {
+                (FrameData::Indexed(img), Some(palette)) if img.palette == *palette => pack_indices(&img.indices, img.width as usize, bit_depth as usize),
+                (FrameData::Color { pixels, .. }, None) if alpha => pixels.iter().flat_map(|p| [p.r, p.g, p.b, p.a]).collect(),
+                (FrameData::Color { pixels, .. }, None) => pixels.iter().flat_map(|p| [p.r, p.g, p.b]).collect(),
+                _ => return Err(String::from("APNG frames have to be either all true color or all indexed with the same palette.")),
+            };
+
+            if animated {
+                let (width, height) = frame.dimensions();
+                let result = writer
+                    .reset_frame_position()
+                    .and_then(|_| writer.set_frame_dimension(width, height))
+                    .and_then(|_| writer.set_frame_position(frame.left, frame.top))
+                    .and_then(|_| writer.set_frame_delay(frame.delay.0, frame.delay.1))
+                    .and_then(|_| {
+                        writer.set_dispose_op(match frame.disposal {
+                            Disposal::Unspecified | Disposal::Keep => png::DisposeOp::None,
+                            Disposal::Background => png::DisposeOp::Background,
+                            Disposal::Previous => png::DisposeOp::Previous,
+                        })
+                    })
+                    .and_then(|_| writer.set_blend_op(if frame.blend { png::BlendOp::Over } else { png::BlendOp::Source }));
+                result.map_err(|err| format!("Failed encoding PNG image: {err}"))?;
+            }
+
+            writer.write_image_data(&data).map_err(|err| format!("Failed encoding PNG image: {err}"))?;
+        }
+
+        writer.finish().map_err(|err| format!("Failed encoding PNG image: {err}"))
+    }
+}
+
+/// Tries to load an animated GIF or APNG image and hide the given secret in its frames.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the animation.
+///
+pub fn hide_secret_animation(img_path: &str, secret: &str, password: Option<SecretString>) -> Result<Animation, String> {
+    let mut animation = Animation::open(img_path)?;
+    hide_secret_frames(&mut animation, secret, password)?;
+    Ok(animation)
+}
+
+/// Tries to load an animated GIF or APNG image and searches its frames for hidden secrets.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for hidden secrets.
+///
+pub fn find_secret_animation(img_path: &str, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let animation = Animation::open(img_path)?;
+    Ok(find_secret_frames(&animation, password))
+}
+
+/// Hides a secret string spread across all frames of an animation.
+///
+/// Every frame which can hold at least a segment header receives one segment. The secret is distributed
+/// in proportion to the frame capacities, each segment starts with its index and the total segment count,
+/// so the secret can be reassembled even if the frames are reordered.
+///
+/// # Arguments
+///
+/// * `animation` - Target animation the secret will be written to.
+/// * `secret` - Secret string which will be hidden in the animation.
+///
+pub fn hide_secret_frames(animation: &mut Animation, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    let secret_bytes = secret_to_bytes(secret, password)?;
+
+    // Pad every palette the same way, including frames too small to carry a segment, so frames sharing a palette keep sharing it.
+    for frame in animation.frames.iter_mut() {
+        if let FrameData::Indexed(img) = &mut frame.data {
+            pad_palette(&mut img.palette);
+        }
+    }
+
+    let carriers: Vec<usize> = (0..animation.frames.len()).filter(|idx| animation.frames[*idx].capacity() >= SEGMENT_HEADER_LEN).collect();
+    let total_capacity: usize = carriers.iter().map(|idx| animation.frames[*idx].capacity() - SEGMENT_HEADER_LEN).sum();
+
+    if total_capacity < secret_bytes.len() || carriers.len() > u16::MAX as usize {
+        return Err(String::from(
+            "The message is too long to be hidden in this animation. Try using a shorter message or a larger input animation.",
+        ));
+    }
+
+    let mut remaining = secret_bytes.expose();
+    let mut remaining_capacity = total_capacity;
+    for (segment_idx, frame_idx) in carriers.iter().enumerate() {
+        let frame = &mut animation.frames[*frame_idx];
+        let frame_capacity = frame.capacity() - SEGMENT_HEADER_LEN;
+
+        // Take at least the proportional share, so the remaining frames can hold the rest.
+        let take = (remaining.len() * frame_capacity).div_ceil(remaining_capacity.max(1)).min(remaining.len());
+        remaining_capacity -= frame_capacity;
+
+        let mut segment = Vec::with_capacity(SEGMENT_HEADER_LEN + take);
+        segment.extend((segment_idx as u16).to_le_bytes());
+        segment.extend((carriers.len() as u16).to_le_bytes());
+        segment.extend(&remaining[..take]);
+        frame.embed(&segment)?;
+
+        remaining = &remaining[take..];
+    }
+
+    Ok(())
+}
+
+/// Returns a secret string reassembled from the frames of an animation if it exists.
+///
+/// # Arguments
+///
+/// * `animation` - Animation from which a secret will be retrieved.
+///
+pub fn find_secret_frames(animation: &Animation, password: Option<SecretString>) -> Option<SecretString> {
+    let mut segments: Vec<Option<Vec<u8>>> = vec![];
+
+    for frame in &animation.frames {
+        let bytes = frame.extract();
+        if bytes.len() < SEGMENT_HEADER_LEN {
+            continue;
+        }
+
+        let segment_idx = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        let segment_count = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+        if segments.is_empty() {
+            segments.resize(segment_count, None);
+        }
+
+        if segment_count != segments.len() || segment_idx >= segment_count {
+            return None;
+        }
+        segments[segment_idx] = Some(bytes[SEGMENT_HEADER_LEN..].to_vec());
+    }
+
+    let bytes: Option<Vec<Vec<u8>>> = segments.into_iter().collect();
+    bytes_to_secret(bytes?.concat(), password)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Returns a three frame animation with varying timing and disposal.
+    fn test_animation(indexed: bool) -> Animation {
+        let frames = (0..3u32)
+            .map(|n| {
+                let (width, height) = (20 - 4 * n, 16 - 2 * n);
+                let data = if indexed {
+                    FrameData::Indexed(IndexedImage {
+                        width,
+                        height,
+                        palette: (0..16).map(|v| Color::from_rgb(v * 16, 255 - v * 16, v)).collect(),
+                        indices: (0..width * height).map(|i| ((i + n) % 16) as u8).collect(),
+                    })
+                } else {
+                    FrameData::Color {
+                        width,
+                        height,
+                        pixels: (0..width * height).map(|i| Color::from_rgba(i as u8, n as u8 * 40, 7, 200 + n as u8)).collect(),
+                    }
+                };
+
+                Frame {
+                    data,
+                    left: n,
+                    top: 2 * n,
+                    delay: (10 * (n as u16 + 1), 100),
+                    disposal: [Disposal::Keep, Disposal::Background, Disposal::Previous][n as usize],
+                    blend: true,
+                }
+            })
+            .collect();
+
+        Animation {
+            format: if indexed { AnimationFormat::Gif } else { AnimationFormat::Apng },
+            width: 20,
+            height: 16,
+            plays: 0,
+            global_palette: None,
+            frames,
+        }
+    }
+
+    #[test]
+    fn encode_decode_secret_frames_ut() {
+        let password = SecretString::from("password");
+
+        for indexed in [true, false] {
+            let mut animation = test_animation(indexed);
+            assert_eq!(None, find_secret_frames(&animation, None));
+
+            // Successfully encode and decode a secret which fills all frames
+            let total_capacity: usize = animation.frames.iter().map(|f| f.capacity() - SEGMENT_HEADER_LEN).sum();
+            let secret: String = "Lorem ipsum dolor sit amet. ".chars().cycle().take(total_capacity).collect();
+            assert!(secret.len() > animation.frames[0].capacity());
+            assert!(hide_secret_frames(&mut animation, &secret, Some(password.to_owned())).is_ok());
+            assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_frames(&animation, Some(password.to_owned())));
+            assert_ne!(Some(SecretString::from(secret.to_owned())), find_secret_frames(&animation, None));
+
+            // The frame order is recorded in the segments
+            animation.frames.reverse();
+            assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_frames(&animation, Some(password.to_owned())));
+
+            // A missing frame returns None
+            animation.frames.pop();
+            assert_eq!(None, find_secret_frames(&animation, Some(password.to_owned())));
+
+            // Return Error when secret is too long for given animation
+            let mut animation = test_animation(indexed);
+            assert!(hide_secret_frames(&mut animation, &format!("{secret}a"), None).is_err());
+
+            // Providing an empty secret returns Error
+            assert!(hide_secret_frames(&mut animation, "", None).is_err());
+        }
+    }
+
+    #[test]
+    fn gif_apng_round_trip_ut() {
+        let secret = String::from("Lorem ipsum dolor sit amet");
+
+        for indexed in [true, false] {
+            let mut animation = test_animation(indexed);
+            assert!(hide_secret_frames(&mut animation, &secret, None).is_ok());
+
+            let mut encodings = vec![];
+            if indexed {
+                let mut buffer = vec![];
+                assert!(animation.write_gif(&mut buffer).is_ok());
+                encodings.push(Animation::read_gif(buffer.as_slice()).unwrap());
+            } else {
+                assert!(animation.write_gif(vec![]).is_err());
+            }
+
+            let mut buffer = vec![];
+            assert!(animation.write_apng(&mut buffer).is_ok());
+            encodings.push(Animation::read_apng(buffer.as_slice()).unwrap());
+
+            for decoded in encodings {
+                assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_frames(&decoded, None));
+                assert_eq!(0, decoded.plays);
+                assert_eq!(animation.frames.len(), decoded.frames.len());
+
+                // Timing, position and disposal are preserved
+                for (frame, decoded_frame) in animation.frames.iter().zip(&decoded.frames) {
+                    assert_eq!(frame.dimensions(), decoded_frame.dimensions());
+                    assert_eq!((frame.left, frame.top), (decoded_frame.left, decoded_frame.top));
+                    assert_eq!(frame.delay_centis(), decoded_frame.delay_centis());
+                    assert_eq!(frame.disposal, decoded_frame.disposal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_and_transparent_frames_ut() {
+        // Fifteen colors with a transparent first entry, so every palette has to be padded
+        let mut palette: Vec<Color> = (0..15).map(|v| Color::from_rgb(v * 16, 255 - v * 16, v)).collect();
+        palette[0].a = 0;
+
+        let mut animation = test_animation(true);
+        for (n, frame) in animation.frames.iter_mut().enumerate() {
+            if let FrameData::Indexed(img) = &mut frame.data {
+                if n == 1 {
+                    (img.width, img.height) = (2, 2);
+                }
+                img.palette = palette.clone();
+                img.indices = (0..img.width * img.height).map(|i| if n == 2 && i % 3 == 0 { 0 } else { (i % 15) as u8 }).collect();
+            }
+        }
+        assert!(animation.frames[1].capacity() < SEGMENT_HEADER_LEN);
+        let original = animation.clone();
+
+        let secret = String::from("Lorem ipsum dolor sit amet");
+        assert!(hide_secret_frames(&mut animation, &secret, None).is_ok());
+
+        let mut gif = vec![];
+        assert!(animation.write_gif(&mut gif).is_ok());
+        let mut apng = vec![];
+        assert!(animation.write_apng(&mut apng).is_ok());
+
+        for decoded in [animation.clone(), Animation::read_gif(gif.as_slice()).unwrap(), Animation::read_apng(apng.as_slice()).unwrap()] {
+            assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_frames(&decoded, None));
+
+            // Transparent pixels stay transparent and visible pixels stay visible
+            for (frame, decoded_frame) in original.frames.iter().zip(&decoded.frames) {
+                if let (FrameData::Indexed(img), FrameData::Indexed(decoded_img)) = (&frame.data, &decoded_frame.data) {
+                    for (idx, decoded_idx) in img.indices.iter().zip(&decoded_img.indices) {
+                        assert_eq!(img.palette[*idx as usize].a == 0, decoded_img.palette[*decoded_idx as usize].a == 0);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hide_find_secret_animation_ut() {
+        // Non existent images return Error
+        assert!(hide_secret_animation("test_images/non_existent_image.gif", "Lorem", None).is_err());
+        assert!(find_secret_animation("test_images/non_existent_image.gif", None).is_err());
+
+        // Still images are loaded as single frame animation
+        let secret = String::from("Lorem ipsum");
+        let result = hide_secret_animation("test_images/peppers.png", &secret, None);
+        assert!(result.is_ok());
+
+        let animation = result.unwrap();
+        assert_eq!(1, animation.frames.len());
+        let path = std::env::temp_dir().join("hips_animation_ut.png");
+        let path = path.to_str().unwrap();
+        assert!(animation.save(path).is_ok());
+        assert_eq!(Ok(Some(SecretString::from(secret))), find_secret_animation(path, None));
+        std::fs::remove_file(path).unwrap();
+    }
+}
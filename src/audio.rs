@@ -0,0 +1,356 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use crate::carrier::{capacity, embed, extract, Carrier};
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::SecretString;
+
+/// Format tag of uncompressed PCM data.
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+
+/// Format tag of the extensible format, the actual format is stored in the sub format.
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Uncompressed 8 or 16 bit PCM audio loaded from a WAV file.
+///
+/// Chunks other than `fmt ` and `data` are kept as they are and written back in their original order.
+#[derive(Clone)]
+pub struct Wav {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// Interleaved sample data as stored in the file, 8 bit samples are unsigned, 16 bit samples are signed little endian.
+    pub data: Vec<u8>,
+    /// All chunks in file order, the `data` chunk is an empty placeholder.
+    chunks: Vec<([u8; 4], Vec<u8>)>,
+}
+
+/// Every sample carries one bit in its least significant bit, i.e. in the low byte of 16 bit samples.
+impl Carrier for Wav {
+    fn sample_count(&self) -> usize {
+        self.data.len() / self.bytes_per_sample()
+    }
+
+    fn sample(&self, idx: usize) -> u8 {
+        self.data[idx * self.bytes_per_sample()]
+    }
+
+    fn set_sample(&mut self, idx: usize, value: u8) {
+        let offset = idx * self.bytes_per_sample();
+        self.data[offset] = value;
+    }
+}
+
+impl Wav {
+    /// Returns a PCM audio clip with a plain `fmt ` chunk.
+    ///
+    /// # Arguments
+    ///
+    /// * `channels` - Number of interleaved channels.
+    /// * `sample_rate` - Samples per second and channel.
+    /// * `bits_per_sample` - Either 8 or 16.
+    /// * `data` - Interleaved sample data.
+    ///
+    pub fn new(channels: u16, sample_rate: u32, bits_per_sample: u16, data: Vec<u8>) -> Result<Wav, String> {
+        if bits_per_sample != 8 && bits_per_sample != 16 {
+            return Err(format!("Only 8 and 16 bit PCM audio is supported, not {bits_per_sample} bit."));
+        }
+
+        let block_align = channels.checked_mul(bits_per_sample / 8);
+        let byte_rate = block_align.and_then(|block_align| sample_rate.checked_mul(block_align as u32));
+        let (Some(block_align), Some(byte_rate)) = (block_align, byte_rate) else {
+            return Err(String::from("The audio format exceeds the limits of the WAV format. Try using fewer channels or a lower sample rate."));
+        };
+
+        let mut fmt = vec![];
+        fmt.extend(WAVE_FORMAT_PCM.to_le_bytes());
+        fmt.extend(channels.to_le_bytes());
+        fmt.extend(sample_rate.to_le_bytes());
+        fmt.extend(byte_rate.to_le_bytes());
+        fmt.extend(block_align.to_le_bytes());
+        fmt.extend(bits_per_sample.to_le_bytes());
+
+        Ok(Wav {
+            channels,
+            sample_rate,
+            bits_per_sample,
+            data,
+            chunks: vec![(*b"fmt ", fmt), (*b"data", vec![])],
+        })
+    }
+
+    /// Tries to load a WAV file.
+    ///
+    /// # Arguments
+    ///
+    /// * `wav_path` - Path to the WAV file.
+    ///
+    pub fn open(wav_path: &str) -> Result<Wav, String> {
+        match File::open(wav_path) {
+            Ok(file) => Wav::read(BufReader::new(file)),
+            Err(_) => Err(format!("Failed loading input audio '{wav_path}'")),
+        }
+    }
+
+    /// Tries to save the audio as WAV file.
+    ///
+    /// # Arguments
+    ///
+    /// * `wav_path` - Path of the target WAV file.
+    ///
+    pub fn save(&self, wav_path: &str) -> Result<(), String> {
+        match File::create(wav_path) {
+            Ok(file) => self.write(BufWriter::new(file)),
+            Err(_) => Err(format!("Failed creating output audio '{wav_path}'")),
+        }
+    }
+
+    /// Tries to decode a WAV file containing 8 or 16 bit PCM audio.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the WAV file.
+    ///
+    pub fn read<R: Read>(mut reader: R) -> Result<Wav, String> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).map_err(|err| format!("Failed reading WAV file: {err}"))?;
+
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(String::from("The file is not a RIFF WAVE file."));
+        }
+
+        let mut chunks = vec![];
+        let mut data = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id: [u8; 4] = bytes[offset..offset + 4].try_into().unwrap();
+            let len = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let body = bytes.get(offset + 8..offset + 8 + len).ok_or_else(|| String::from("The WAV file is truncated."))?;
+
+            if &id == b"data" {
+                data = Some(body.to_vec());
+                chunks.push((id, vec![]));
+            } else {
+                chunks.push((id, body.to_vec()));
+            }
+
+            // Chunks are padded to an even length
+            offset += 8 + len + len % 2;
+        }
+
+        let fmt = match chunks.iter().find(|(id, _)| id == b"fmt ") {
+            Some((_, fmt)) if fmt.len() >= 16 => fmt,
+            _ => return Err(String::from("The WAV file does not contain a valid format chunk.")),
+        };
+
+        let mut format = u16::from_le_bytes([fmt[0], fmt[1]]);
+        if format == WAVE_FORMAT_EXTENSIBLE && fmt.len() >= 26 {
+            format = u16::from_le_bytes([fmt[24], fmt[25]]);
+        }
+        let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+        let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+        let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]);
+
+        if format != WAVE_FORMAT_PCM || (bits_per_sample != 8 && bits_per_sample != 16) {
+            return Err(String::from("Only uncompressed 8 and 16 bit PCM audio is supported."));
+        }
+
+        match data {
+            Some(data) => Ok(Wav {
+                channels,
+                sample_rate,
+                bits_per_sample,
+                data,
+                chunks,
+            }),
+            None => Err(String::from("The WAV file does not contain a data chunk.")),
+        }
+    }
+
+    /// Tries to encode the audio as WAV file.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Target of the WAV file.
+    ///
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), String> {
+        let mut body = b"WAVE".to_vec();
+        for (id, chunk) in &self.chunks {
+            let chunk = if id == b"data" { &self.data } else { chunk };
+            body.extend(id);
+            body.extend((chunk.len() as u32).to_le_bytes());
+            body.extend(chunk);
+            if chunk.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((body.len() as u32).to_le_bytes());
+        bytes.extend(body);
+
+        writer.write_all(&bytes).and_then(|_| writer.flush()).map_err(|err| format!("Failed writing WAV file: {err}"))
+    }
+
+    /// Returns the number of bytes used by a single sample.
+    fn bytes_per_sample(&self) -> usize {
+        self.bits_per_sample as usize / 8
+    }
+}
+
+/// Tries to load a WAV file and hide the given secret in its samples.
+///
+/// # Arguments
+///
+/// * `wav_path` - Path to the target WAV file.
+/// * `secret` - Secret text which will be hidden in the audio.
+///
+pub fn hide_secret_wav(wav_path: &str, secret: &str, password: Option<SecretString>) -> Result<Wav, String> {
+    let mut wav = Wav::open(wav_path)?;
+    hide_secret_pcm(&mut wav, secret, password)?;
+    Ok(wav)
+}
+
+/// Tries to load a WAV file and searches its samples for hidden secrets.
+///
+/// # Arguments
+///
+/// * `wav_path` - Path to the WAV file which will be searched for hidden secrets.
+///
+pub fn find_secret_wav(wav_path: &str, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let wav = Wav::open(wav_path)?;
+    Ok(find_secret_pcm(&wav, password))
+}
+
+/// Hides a secret string in the least significant bits of the PCM samples.
+///
+/// # Arguments
+///
+/// * `wav` - Target audio the secret will be written to.
+/// * `secret` - Secret string which will be hidden in the audio.
+///
+pub fn hide_secret_pcm(wav: &mut Wav, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    let secret_bytes = secret_to_bytes(secret, password)?;
+
+    if capacity(wav.sample_count()) < secret_bytes.len() {
+        return Err(String::from("The message is too long to be hidden in this audio. Try using a shorter message or a longer audio clip."));
+    }
+
+    embed(wav, &secret_bytes).map(|_| ())
+}
+
+/// Returns a secret string retrieved from the least significant bits of the PCM samples if it exists.
+///
+/// # Arguments
+///
+/// * `wav` - Audio from which a secret will be retrieved.
+///
+pub fn find_secret_pcm(wav: &Wav, password: Option<SecretString>) -> Option<SecretString> {
+    bytes_to_secret(extract(wav), password)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Returns a short sine tone with the given sample width.
+    fn test_wav(bits_per_sample: u16) -> Wav {
+        let samples = (0..2000).map(|n| (n as f32 / 10.0).sin());
+        let data = match bits_per_sample {
+            8 => samples.map(|s| (128.0 + 100.0 * s) as u8).collect(),
+            _ => samples.flat_map(|s| ((20000.0 * s) as i16).to_le_bytes()).collect(),
+        };
+        Wav::new(2, 44100, bits_per_sample, data).unwrap()
+    }
+
+    #[test]
+    fn encode_decode_secret_pcm_ut() {
+        let secret = String::from("Lorem ipsum dolor sit amet");
+        let password = SecretString::from("password");
+
+        for bits_per_sample in [8, 16] {
+            let mut wav = test_wav(bits_per_sample);
+            let original = wav.clone();
+            assert_eq!(None, find_secret_pcm(&wav, None));
+
+            // Successfully encode and decode a valid secret
+            assert!(hide_secret_pcm(&mut wav, &secret, Some(password.to_owned())).is_ok());
+            assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_pcm(&wav, Some(password.to_owned())));
+            assert_ne!(Some(SecretString::from(secret.to_owned())), find_secret_pcm(&wav, None));
+
+            // Samples change by at most one
+            if bits_per_sample == 16 {
+                for (before, after) in original.data.chunks(2).zip(wav.data.chunks(2)) {
+                    let before = i16::from_le_bytes([before[0], before[1]]);
+                    let after = i16::from_le_bytes([after[0], after[1]]);
+                    assert!((before as i32 - after as i32).abs() <= 1);
+                }
+            } else {
+                assert!(original.data.iter().zip(&wav.data).all(|(b, a)| b.abs_diff(*a) <= 1));
+            }
+
+            // Return Error when secret is too long for given audio
+            let too_long = "a".repeat(wav.sample_count() / 9 + 1);
+            assert!(hide_secret_pcm(&mut wav, &too_long, None).is_err());
+
+            // Providing an empty secret returns Error
+            assert!(hide_secret_pcm(&mut wav, "", None).is_err());
+        }
+
+        // Only 8 and 16 bit audio is supported
+        assert!(Wav::new(1, 8000, 24, vec![]).is_err());
+
+        // Block align and byte rate have to fit into their header fields
+        assert!(Wav::new(u16::MAX, 8000, 16, vec![]).is_err());
+        assert!(Wav::new(2, u32::MAX, 16, vec![]).is_err());
+        assert!(Wav::new(u16::MAX, 1, 8, vec![]).is_ok());
+    }
+
+    #[test]
+    fn read_write_wav_ut() {
+        let secret = String::from("Lorem ipsum");
+        let mut wav = test_wav(16);
+        wav.chunks.insert(1, (*b"LIST", b"odd".to_vec()));
+        assert!(hide_secret_pcm(&mut wav, &secret, None).is_ok());
+
+        // Samples and unknown chunks survive writing and reading the file
+        let mut buffer = vec![];
+        assert!(wav.write(&mut buffer).is_ok());
+        let decoded = Wav::read(buffer.as_slice()).unwrap();
+        assert_eq!((2, 44100, 16), (decoded.channels, decoded.sample_rate, decoded.bits_per_sample));
+        assert_eq!(wav.data, decoded.data);
+        assert_eq!(wav.chunks, decoded.chunks);
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_pcm(&decoded, None));
+
+        // Files which are not WAV files return Error
+        assert!(Wav::read(&b"RIFF\x04\x00\x00\x00AVI "[..]).is_err());
+
+        // Compressed audio returns Error
+        let mut buffer = vec![];
+        assert!(wav.write(&mut buffer).is_ok());
+        buffer[20] = 3;
+        assert!(Wav::read(buffer.as_slice()).is_err());
+    }
+
+    #[test]
+    fn hide_find_secret_wav_ut() {
+        // Non existent files return Error
+        assert!(hide_secret_wav("test_images/non_existent_audio.wav", "Lorem", None).is_err());
+        assert!(find_secret_wav("test_images/non_existent_audio.wav", None).is_err());
+
+        // Files which are not WAV files return Error
+        assert!(find_secret_wav("test_images/peppers.png", None).is_err());
+
+        // Successfully hide and find a secret in a WAV file
+        let secret = String::from("Lorem ipsum");
+        let path = std::env::temp_dir().join("hips_audio_ut.wav");
+        let path = path.to_str().unwrap();
+        assert!(test_wav(8).save(path).is_ok());
+
+        let result = hide_secret_wav(path, &secret, None);
+        assert!(result.is_ok());
+        assert!(result.unwrap().save(path).is_ok());
+        assert_eq!(Ok(Some(SecretString::from(secret))), find_secret_wav(path, None));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,11 +1,39 @@
-pub trait BitOps {
+/// Access to single bits and fields of bits of unsigned integers, implemented for `u8` up to `u128`.
+///
+/// Positions are counted from the least significant bit. The plain methods panic if a position is outside
+/// of the number, the checked methods return None instead.
+pub trait BitOps: Sized {
     /// Returns the bit on the specified position.
     ///
-    fn get_bit(&self, n: u8) -> bool;
+    fn get_bit(&self, n: u32) -> bool;
 
     /// Sets a bit on the specified position.
     ///
-    fn set_bit(&self, n: usize, value: bool) -> u8;
+    fn set_bit(&self, n: u32, value: bool) -> Self;
+
+    /// Returns the bit on the specified position, or None if the position is outside of the number.
+    ///
+    fn checked_get_bit(&self, n: u32) -> Option<bool>;
+
+    /// Sets a bit on the specified position, or returns None if the position is outside of the number.
+    ///
+    fn checked_set_bit(&self, n: u32, value: bool) -> Option<Self>;
+
+    /// Returns `len` bits starting at position `start` as number, e.g. the lowest bits used with a depth above one.
+    ///
+    fn get_bits(&self, start: u32, len: u32) -> Self;
+
+    /// Replaces `len` bits starting at position `start` with the lowest bits of the value.
+    ///
+    fn set_bits(&self, start: u32, len: u32, value: Self) -> Self;
+
+    /// Returns `len` bits starting at position `start`, or None if the bits exceed the number.
+    ///
+    fn checked_get_bits(&self, start: u32, len: u32) -> Option<Self>;
+
+    /// Replaces `len` bits starting at position `start`, or returns None if the bits exceed the number.
+    ///
+    fn checked_set_bits(&self, start: u32, len: u32, value: Self) -> Option<Self>;
 
     /// Sets the least significant bit of a number according to the passed value.
     ///
@@ -16,12 +44,164 @@ pub trait BitOps {
     fn get_lsb(&self) -> bool;
 }
 
+/// Implements [`BitOps`] for unsigned integers. The operations are available as `const fn` in a module per type
+/// as well, e.g. [`u8_bits::set_bits`], as trait methods cannot be `const`.
+macro_rules! impl_bit_ops {
+    ($($t:ident => $module:ident),*) => {$(
+        #[doc = concat!("Const implementations of [`BitOps`] for `", stringify!($t), "`.")]
+        pub mod $module {
+            /// Returns a number with the lowest `len` bits set.
+            const fn mask(len: u32) -> $t {
+                if len >= $t::BITS {
+                    $t::MAX
+                } else {
+                    (1 << len) - 1
+                }
+            }
+
+            /// Returns whether `len` bits starting at position `start` fit in the number.
+            const fn fits(start: u32, len: u32) -> bool {
+                start <= $t::BITS && len <= $t::BITS - start
+            }
+
+            pub const fn checked_get_bit(value: $t, n: u32) -> Option<bool> {
+                if n >= $t::BITS {
+                    return None;
+                }
+                Some(value >> n & 1 == 1)
+            }
+
+            pub const fn checked_set_bit(value: $t, n: u32, bit: bool) -> Option<$t> {
+                if n >= $t::BITS {
+                    return None;
+                }
+                Some(value & !(1 << n) | (bit as $t) << n)
+            }
+
+            pub const fn checked_get_bits(value: $t, start: u32, len: u32) -> Option<$t> {
+                if !fits(start, len) {
+                    return None;
+                }
+                if len == 0 {
+                    return Some(0);
+                }
+                Some(value >> start & mask(len))
+            }
+
+            pub const fn checked_set_bits(value: $t, start: u32, len: u32, bits: $t) -> Option<$t> {
+                if !fits(start, len) {
+                    return None;
+                }
+                if len == 0 {
+                    return Some(value);
+                }
+                let mask = mask(len) << start;
+                Some(value & !mask | bits << start & mask)
+            }
+
+            pub const fn get_bit(value: $t, n: u32) -> bool {
+                match checked_get_bit(value, n) {
+                    Some(bit) => bit,
+                    None => panic!("Overflow detected while using `get_bit`."),
+                }
+            }
+
+            pub const fn set_bit(value: $t, n: u32, bit: bool) -> $t {
+                match checked_set_bit(value, n, bit) {
+                    Some(value) => value,
+                    None => panic!("Overflow detected while using `set_bit`."),
+                }
+            }
+
+            pub const fn get_bits(value: $t, start: u32, len: u32) -> $t {
+                match checked_get_bits(value, start, len) {
+                    Some(bits) => bits,
+                    None => panic!("Overflow detected while using `get_bits`."),
+                }
+            }
+
+            pub const fn set_bits(value: $t, start: u32, len: u32, bits: $t) -> $t {
+                match checked_set_bits(value, start, len, bits) {
+                    Some(value) => value,
+                    None => panic!("Overflow detected while using `set_bits`."),
+                }
+            }
+
+            pub const fn set_lsb(value: $t, bit: bool) -> $t {
+                value & !1 | bit as $t
+            }
+
+            pub const fn get_lsb(value: $t) -> bool {
+                value & 1 == 1
+            }
+        }
+
+        impl BitOps for $t {
+            fn get_bit(&self, n: u32) -> bool {
+                match $module::checked_get_bit(*self, n) {
+                    Some(bit) => bit,
+                    None => panic!("Overflow detected while using `get_bit`: Tried to get the {n}th bit."),
+                }
+            }
+
+            fn set_bit(&self, n: u32, value: bool) -> $t {
+                match $module::checked_set_bit(*self, n, value) {
+                    Some(result) => result,
+                    None => panic!("Overflow detected while using `set_bit`: Tried to set the {n}th bit."),
+                }
+            }
+
+            fn checked_get_bit(&self, n: u32) -> Option<bool> {
+                $module::checked_get_bit(*self, n)
+            }
+
+            fn checked_set_bit(&self, n: u32, value: bool) -> Option<$t> {
+                $module::checked_set_bit(*self, n, value)
+            }
+
+            fn get_bits(&self, start: u32, len: u32) -> $t {
+                match $module::checked_get_bits(*self, start, len) {
+                    Some(bits) => bits,
+                    None => panic!("Overflow detected while using `get_bits`: Tried to get {len} bits from the {start}th bit."),
+                }
+            }
+
+            fn set_bits(&self, start: u32, len: u32, value: $t) -> $t {
+                match $module::checked_set_bits(*self, start, len, value) {
+                    Some(result) => result,
+                    None => panic!("Overflow detected while using `set_bits`: Tried to set {len} bits from the {start}th bit."),
+                }
+            }
+
+            fn checked_get_bits(&self, start: u32, len: u32) -> Option<$t> {
+                $module::checked_get_bits(*self, start, len)
+            }
+
+            fn checked_set_bits(&self, start: u32, len: u32, value: $t) -> Option<$t> {
+                $module::checked_set_bits(*self, start, len, value)
+            }
+
+            fn set_lsb(&self, value: bool) -> $t {
+                $module::set_lsb(*self, value)
+            }
+
+            fn get_lsb(&self) -> bool {
+                $module::get_lsb(*self)
+            }
+        }
+    )*};
+}
+
+impl_bit_ops!(u8 => u8_bits, u16 => u16_bits, u32 => u32_bits, u64 => u64_bits, u128 => u128_bits);
+
 pub trait BitBuffer {
     /// Returns a vector of bools representing the single bits of self.
     ///
+    #[deprecated(note = "Allocates a bool per bit, use `BitReader` instead.")]
     fn to_bit_buffer(&self) -> Vec<bool>;
 }
 
+#[allow(deprecated)]
 impl BitBuffer for u8 {
     fn to_bit_buffer(&self) -> Vec<bool> {
         let mut result = vec![];
@@ -34,43 +214,185 @@ impl BitBuffer for u8 {
     }
 }
 
-impl BitOps for u8 {
-    fn get_bit(&self, n: u8) -> bool {
-        if n > 7 {
-            panic!("Overflow detected while using `get_bit`: Tried to get the {n}th bit.");
+/// Order in which the bits of every byte are visited by [`BitReader`] and [`BitWriter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The least significant bit comes first, the order of all carriers.
+    #[default]
+    LsbFirst,
+    /// The most significant bit comes first.
+    MsbFirst,
+}
+
+impl BitOrder {
+    /// Returns the position within a byte of the n-th visited bit.
+    #[inline]
+    const fn shift(self, n: usize) -> usize {
+        match self {
+            BitOrder::LsbFirst => n % 8,
+            BitOrder::MsbFirst => 7 - n % 8,
         }
-        let result = *self >> n & 1;
+    }
+}
+
+/// Reads single bits from packed bytes without expanding them.
+///
+/// Iterating the reader returns all bits in order.
+#[derive(Clone, Debug)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    order: BitOrder,
+    /// Number of bits read so far.
+    position: usize,
+}
 
-        result == 1
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8], order: BitOrder) -> Self {
+        BitReader { bytes, order, position: 0 }
     }
 
-    fn set_bit(&self, n: usize, value: bool) -> u8 {
-        let mut result = *self;
+    /// Returns the number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of bits which are left.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
 
-        if n > 7 {
-            panic!("Overflow detected while using `set_bit`: Tried to set the {n}th bit.");
+    /// Returns the next bit, or None if all bits were read.
+    #[inline]
+    pub fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.position / 8)?;
+        let bit = byte >> self.order.shift(self.position) & 1 == 1;
+        self.position += 1;
+        Some(bit)
+    }
+
+    /// Returns the next `count` bits as number, the first bit being the least significant one for
+    /// [`BitOrder::LsbFirst`] and the most significant one for [`BitOrder::MsbFirst`].
+    /// Returns None without reading anything if less bits are left.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of bits to read, at most 64.
+    ///
+    pub fn read_bits(&mut self, count: usize) -> Option<u64> {
+        if count > 64 || count > self.remaining() {
+            return None;
         }
 
-        if value {
-            result |= 1 << n;
-        } else {
-            result &= !(1 << n);
+        let mut value = 0;
+        for n in 0..count {
+            let bit = self.read_bit()? as u64;
+            value |= match self.order {
+                BitOrder::LsbFirst => bit << n,
+                BitOrder::MsbFirst => bit << (count - 1 - n),
+            };
         }
-        result
+        Some(value)
+    }
+}
+
+impl Iterator for BitReader<'_> {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<bool> {
+        self.read_bit()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining(), Some(self.remaining()))
+    }
+}
+
+impl ExactSizeIterator for BitReader<'_> {}
+
+/// Collects single bits into packed bytes. The last byte is padded with zeros.
+#[derive(Clone, Debug, Default)]
+pub struct BitWriter {
+    /// All complete bytes.
+    bytes: Vec<u8>,
+    /// Byte which is currently assembled.
+    current: u8,
+    order: BitOrder,
+    /// Number of bits written so far.
+    len: usize,
+}
+
+impl BitWriter {
+    pub fn new(order: BitOrder) -> Self {
+        BitWriter::with_capacity(0, order)
     }
 
-    fn set_lsb(&self, value: bool) -> u8 {
-        let mut result = *self;
-        if value {
-            result |= 0b0000_0001;
-            return result;
+    /// Returns a writer with space for the given number of bits.
+    pub fn with_capacity(bits: usize, order: BitOrder) -> Self {
+        BitWriter {
+            bytes: Vec::with_capacity(bits.div_ceil(8)),
+            current: 0,
+            order,
+            len: 0,
         }
-        result &= 0b1111_1110;
-        result
     }
 
-    fn get_lsb(&self) -> bool {
-        self % 2 != 0
+    /// Returns the number of bits written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a single bit.
+    #[inline]
+    pub fn write_bit(&mut self, bit: bool) {
+        self.current |= (bit as u8) << self.order.shift(self.len);
+        self.len += 1;
+
+        if self.len % 8 == 0 {
+            self.bytes.push(self.current);
+            self.current = 0;
+        }
+    }
+
+    /// Appends the lowest `count` bits of the value, in the order [`BitReader::read_bits`] reads them.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Number whose bits are appended.
+    /// * `count` - Number of bits to append, at most 64.
+    ///
+    pub fn write_bits(&mut self, value: u64, count: usize) {
+        for n in 0..count.min(64) {
+            let shift = match self.order {
+                BitOrder::LsbFirst => n,
+                BitOrder::MsbFirst => count - 1 - n,
+            };
+            self.write_bit(value >> shift & 1 == 1);
+        }
+    }
+
+    /// Removes all written bits.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.current = 0;
+        self.len = 0;
+    }
+
+    /// Returns all complete bytes written so far.
+    pub fn complete_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns all written bytes, including an incomplete last byte.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        if self.len % 8 != 0 {
+            self.bytes.push(self.current);
+        }
+        self.bytes
     }
 }
 
@@ -80,67 +402,185 @@ mod tests {
 
     #[test]
     fn set_lsb_ut() {
-        assert_eq!(1, 0.set_lsb(true));
-        assert_eq!(0, 0.set_lsb(false));
-        assert_eq!(0, 1.set_lsb(false));
-        assert_eq!(1, 1.set_lsb(true));
+        assert_eq!(1, 0u8.set_lsb(true));
+        assert_eq!(0, 0u8.set_lsb(false));
+        assert_eq!(0, 1u8.set_lsb(false));
+        assert_eq!(1, 1u8.set_lsb(true));
 
-        assert_eq!(254, 255.set_lsb(false));
-        assert_eq!(255, 255.set_lsb(true));
+        assert_eq!(254, 255u8.set_lsb(false));
+        assert_eq!(255, 255u8.set_lsb(true));
     }
 
     #[test]
     fn get_lsb_ut() {
-        assert!(1.get_lsb());
-        assert!(!0.get_lsb());
-        assert!(11.get_lsb());
-        assert!(!22.get_lsb());
+        assert!(1u8.get_lsb());
+        assert!(!0u8.get_lsb());
+        assert!(11u8.get_lsb());
+        assert!(!22u8.get_lsb());
     }
 
     #[test]
     fn set_bit_ut() {
         // Test setting the last bit
-        assert_eq!(0b1000_0000, 0b0000_0000.set_bit(7, true));
-        assert_eq!(0b0000_0000, 0b1000_0000.set_bit(7, false));
-        assert_eq!(0b1000_0000, 0b1000_0000.set_bit(7, true));
-        assert_eq!(0b0000_0000, 0b0000_0000.set_bit(7, false));
+        assert_eq!(0b1000_0000, 0b0000_0000u8.set_bit(7, true));
+        assert_eq!(0b0000_0000, 0b1000_0000u8.set_bit(7, false));
+        assert_eq!(0b1000_0000, 0b1000_0000u8.set_bit(7, true));
+        assert_eq!(0b0000_0000, 0b0000_0000u8.set_bit(7, false));
 
         // Test setting first bits
-        assert_eq!(0b0000_0001, 0b0000_0000.set_bit(0, true));
-        assert_eq!(0b0000_0000, 0b0000_0001.set_bit(0, false));
-        assert_eq!(0b0000_0001, 0b0000_0001.set_bit(0, true));
-        assert_eq!(0b0000_0000, 0b0000_0000.set_bit(0, false));
+        assert_eq!(0b0000_0001, 0b0000_0000u8.set_bit(0, true));
+        assert_eq!(0b0000_0000, 0b0000_0001u8.set_bit(0, false));
+        assert_eq!(0b0000_0001, 0b0000_0001u8.set_bit(0, true));
+        assert_eq!(0b0000_0000, 0b0000_0000u8.set_bit(0, false));
 
         // Test setting some random bits
-        assert_eq!(0b0000_1000, 0b0000_0000.set_bit(3, true));
-        assert_eq!(0b0110_1000, 0b0100_1000.set_bit(5, true));
-        assert_eq!(0b0000_0000, 0b0000_1000.set_bit(3, false));
-        assert_eq!(0b1100_1011, 0b1100_1111.set_bit(2, false));
+        assert_eq!(0b0000_1000, 0b0000_0000u8.set_bit(3, true));
+        assert_eq!(0b0110_1000, 0b0100_1000u8.set_bit(5, true));
+        assert_eq!(0b0000_0000, 0b0000_1000u8.set_bit(3, false));
+        assert_eq!(0b1100_1011, 0b1100_1111u8.set_bit(2, false));
     }
 
     #[test]
     fn get_bit_ut() {
-        assert!(0b0000_0001.get_bit(0));
-        assert!(0b0000_0010.get_bit(1));
-        assert!(0b0000_0100.get_bit(2));
-        assert!(0b0000_1000.get_bit(3));
-        assert!(0b0001_0000.get_bit(4));
-        assert!(0b0010_0000.get_bit(5));
-        assert!(0b0100_0000.get_bit(6));
-        assert!(0b1000_0000.get_bit(7));
+        assert!(0b0000_0001u8.get_bit(0));
+        assert!(0b0000_0010u8.get_bit(1));
+        assert!(0b0000_0100u8.get_bit(2));
+        assert!(0b0000_1000u8.get_bit(3));
+        assert!(0b0001_0000u8.get_bit(4));
+        assert!(0b0010_0000u8.get_bit(5));
+        assert!(0b0100_0000u8.get_bit(6));
+        assert!(0b1000_0000u8.get_bit(7));
+    }
+
+    #[test]
+    fn wide_types_ut() {
+        assert_eq!(0x8000, 0u16.set_bit(15, true));
+        assert!(0x8000_0000u32.get_bit(31));
+        assert_eq!(u64::MAX - 1, u64::MAX.set_lsb(false));
+        assert!(1u128.set_bit(127, true).get_bit(127));
+        assert!(std::panic::catch_unwind(|| 0u16.get_bit(16)).is_err());
+        assert!(std::panic::catch_unwind(|| 0u128.set_bit(128, true)).is_err());
+    }
+
+    #[test]
+    fn checked_ut() {
+        assert_eq!(Some(true), 0b1000_0000u8.checked_get_bit(7));
+        assert_eq!(None, 0u8.checked_get_bit(8));
+        assert_eq!(Some(0b0000_0100), 0u8.checked_set_bit(2, true));
+        assert_eq!(None, 0u8.checked_set_bit(8, true));
+        assert_eq!(None, 0u64.checked_get_bit(u32::MAX));
+
+        assert_eq!(Some(0b11), 0b1100u8.checked_get_bits(2, 2));
+        assert_eq!(None, 0u8.checked_get_bits(7, 2));
+        assert_eq!(None, 0u8.checked_set_bits(u32::MAX, 2, 0));
+    }
+
+    #[test]
+    fn bits_ut() {
+        // The lowest bits as used with a depth above one
+        assert_eq!(0b101, 0b1010_1101u8.get_bits(0, 3));
+        assert_eq!(0b1010_1010, 0b1010_1101u8.set_bits(0, 3, 0b010));
+        assert_eq!(0b1010, 0b1010_1101u8.get_bits(4, 4));
+
+        // Bits of the value above the length are ignored
+        assert_eq!(0b0000_0110, 0u8.set_bits(1, 2, 0b1111_1111));
+
+        // Empty and complete fields
+        assert_eq!(0, 0xFFu8.get_bits(8, 0));
+        assert_eq!(0xAB, 0xABu8.set_bits(3, 0, 0xFF));
+        assert_eq!(u128::MAX, 0u128.set_bits(0, 128, u128::MAX));
+        assert_eq!(0x1234, 0x1234_5678u32.get_bits(16, 16));
+
+        assert!(std::panic::catch_unwind(|| 0u8.get_bits(4, 5)).is_err());
+        assert!(std::panic::catch_unwind(|| 0u32.set_bits(30, 3, 0)).is_err());
+    }
+
+    #[test]
+    fn const_ut() {
+        const FIELD: u16 = u16_bits::set_bits(0, 4, 4, 0b1001);
+        const BIT: bool = u16_bits::get_bit(FIELD, 7);
+        const CHECKED: Option<bool> = u8_bits::checked_get_bit(0, 8);
+        const LSB: u8 = u8_bits::set_lsb(254, true);
+
+        assert_eq!(0b1001_0000, FIELD);
+        const { assert!(BIT) };
+        assert_eq!(None, CHECKED);
+        assert_eq!(255, LSB);
+        assert_eq!(0b1001, u16_bits::get_bits(FIELD, 4, 4));
+        assert!(u64_bits::get_lsb(1) && !u32_bits::get_lsb(2) && u128_bits::set_bit(0, 100, true) == 1 << 100);
+    }
+
+    #[test]
+    fn bit_reader_ut() {
+        let bytes = [0b1000_0001, 0b0000_0110];
+
+        let bits: Vec<bool> = BitReader::new(&bytes, BitOrder::LsbFirst).take(10).collect();
+        assert_eq!(vec![true, false, false, false, false, false, false, true, false, true], bits);
+        let bits: Vec<bool> = BitReader::new(&bytes, BitOrder::MsbFirst).take(10).collect();
+        assert_eq!(vec![true, false, false, false, false, false, false, true, false, false], bits);
+
+        let mut reader = BitReader::new(&bytes, BitOrder::LsbFirst);
+        assert_eq!(16, reader.len());
+        assert_eq!(Some(0b01), reader.read_bits(2));
+        assert_eq!(Some(0b01_1010_0000), reader.read_bits(10));
+        assert_eq!(12, reader.position());
+
+        // Reading beyond the end returns None without consuming bits
+        assert_eq!(None, reader.read_bits(5));
+        assert_eq!(Some(0), reader.read_bits(4));
+        assert_eq!(None, reader.read_bit());
+
+        let mut reader = BitReader::new(&bytes, BitOrder::MsbFirst);
+        assert_eq!(Some(0b1000_0001_0000), reader.read_bits(12));
+        assert_eq!(None, reader.read_bits(65));
+    }
+
+    #[test]
+    fn bit_writer_ut() {
+        for order in [BitOrder::LsbFirst, BitOrder::MsbFirst] {
+            let mut writer = BitWriter::with_capacity(20, order);
+            assert!(writer.is_empty());
+            writer.write_bits(0b101, 3);
+            writer.write_bit(true);
+            writer.write_bits(0xABCD, 16);
+            assert_eq!(20, writer.len());
+            assert_eq!(2, writer.complete_bytes().len());
+
+            // The reader returns the written bits
+            let bytes = writer.into_bytes();
+            assert_eq!(3, bytes.len());
+            let mut reader = BitReader::new(&bytes, order);
+            assert_eq!(Some(0b101), reader.read_bits(3));
+            assert_eq!(Some(true), reader.read_bit());
+            assert_eq!(Some(0xABCD), reader.read_bits(16));
+            assert_eq!(Some(0), reader.read_bits(4));
+        }
+
+        let mut writer = BitWriter::new(BitOrder::LsbFirst);
+        writer.write_bits(u64::MAX, 12);
+        writer.clear();
+        assert!(writer.is_empty() && writer.into_bytes().is_empty());
+
+        let mut writer = BitWriter::new(BitOrder::LsbFirst);
+        writer.write_bits(0b1011_0001, 8);
+        assert_eq!(vec![0b1011_0001], writer.into_bytes());
+        let mut writer = BitWriter::new(BitOrder::MsbFirst);
+        writer.write_bits(0b1011_0001, 8);
+        writer.write_bit(true);
+        assert_eq!(vec![0b1011_0001, 0b1000_0000], writer.into_bytes());
     }
 
     #[test]
     fn get_bit_panic_ut() {
         for i in 8..=255 {
-            assert!(std::panic::catch_unwind(|| 0b0000_0000.get_bit(i)).is_err());
+            assert!(std::panic::catch_unwind(|| 0b0000_0000u8.get_bit(i)).is_err());
         }
     }
 
     #[test]
     fn set_bit_panic_ut() {
         for i in 8..=255 {
-            assert!(std::panic::catch_unwind(|| 0b0000_0000.set_bit(i, false)).is_err());
+            assert!(std::panic::catch_unwind(|| 0b0000_0000u8.set_bit(i, false)).is_err());
         }
     }
 }
@@ -0,0 +1,206 @@
+use crate::carrier::{capacity, embed, extract, Carrier};
+use crate::color::Color;
+use crate::crypto::{cover_hash, derive_key, open_aad, random_bytes, seal_aad, NONCE_LEN, SALT_LEN, TAG_LEN};
+use crate::secret::SecretString;
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// Marks a payload as bound to its cover, followed by the format version.
+const MAGIC: &[u8; 4] = b"HCB\x02";
+
+/// Context of the keys derived from the passwords.
+const KEY_CONTEXT: &str = "hips-lib bound";
+
+/// Number of bytes of the cover hash behind the magic bytes.
+const HASH_LEN: usize = 32;
+
+/// Number of bytes in front of every bound secret: magic bytes, the cover hash, the salt of the key and the nonce.
+pub const BOUND_HEADER_LEN: usize = MAGIC.len() + HASH_LEN + SALT_LEN + NONCE_LEN;
+
+/// Result of comparing the cover a secret was hidden in with the carrier it was retrieved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverCheck {
+    /// The secret was retrieved from the cover it was hidden in.
+    Match,
+    /// The least significant bits were copied onto another cover, or the high bits of the cover were modified.
+    Mismatch,
+}
+
+/// A secret retrieved from a carrier together with the result of checking its cover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundSecret {
+    pub secret: SecretString,
+    pub cover: CoverCheck,
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and hide the given secret in it, bound to the image, see [`hide_secret_col_bound`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the image.
+/// * `password` - Password the secret will be encrypted with.
+///
+pub fn hide_secret_img_bound(img_path: &str, secret: &str, password: SecretString) -> Result<DynamicImage, String> {
+    let mut img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    hide_bound(&mut img, secret, &password)?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and searches it for a secret bound to its cover, see [`find_secret_col_bound`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for hidden secrets.
+/// * `password` - Password the secret was encrypted with.
+///
+pub fn find_secret_img_bound(img_path: &str, password: SecretString) -> Result<Option<BoundSecret>, String> {
+    let img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    Ok(find_bound(&img, &password))
+}
+
+/// Hides an encrypted secret in a vector of pixels, bound to the pixels it is hidden in.
+///
+/// A hash over all bits which are never touched by hiding is stored in front of the secret and
+/// authenticated together with it, so it cannot be replaced without the password.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secret will be hidden in.
+/// * `secret` - The secret string.
+/// * `password` - Password the secret will be encrypted with.
+///
+pub fn hide_secret_col_bound(pixels: &mut [Color], secret: &str, password: SecretString) -> Result<(), String> {
+    hide_bound(pixels, secret, &password)
+}
+
+/// Returns a secret bound to its cover retrieved from the provided pixel vector if it exists.
+/// The result reports a mismatch if the secret was hidden in other pixels.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels which will be searched for a secret string.
+/// * `password` - Password the secret was encrypted with.
+///
+pub fn find_secret_col_bound(pixels: &[Color], password: SecretString) -> Option<BoundSecret> {
+    find_bound(pixels, &password)
+}
+
+fn hide_bound<C: Carrier + ?Sized>(carrier: &mut C, secret: &str, password: &str) -> Result<(), String> {
+    if secret.is_empty() {
+        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
+    }
+    if password.is_empty() {
+        return Err(String::from("You have entered an empty password. Try to use at least one character in the password."));
+    }
+
+    if capacity(carrier.sample_count()) < BOUND_HEADER_LEN + secret.len() + TAG_LEN {
+        return Err(String::from("The message is too long to be hidden in this carrier. Try using a shorter message or a larger carrier."));
+    }
+
+    let hash = cover_hash(carrier);
+    let mut payload = MAGIC.to_vec();
+    payload.extend(hash);
+    let salt = random_bytes(SALT_LEN)?;
+    payload.extend(&salt);
+    payload.extend(seal_aad(&derive_key(password, KEY_CONTEXT, &salt), secret.as_bytes(), &hash)?);
+
+    embed(carrier, &payload).map(|_| ())
+}
+
+fn find_bound<C: Carrier + ?Sized>(carrier: &C, password: &str) -> Option<BoundSecret> {
+    let payload = extract(carrier);
+    if payload.len() < BOUND_HEADER_LEN + TAG_LEN || !payload.starts_with(MAGIC) {
+        return None;
+    }
+
+    // The stored hash is authenticated, so a mismatch means the cover changed and not the hash
+    let (hash, rest) = payload[MAGIC.len()..].split_at(HASH_LEN);
+    let (salt, sealed) = rest.split_at(SALT_LEN);
+    let plaintext = open_aad(&derive_key(password, KEY_CONTEXT, salt), sealed, hash)?;
+
+    let cover = if cover_hash(carrier) == hash { CoverCheck::Match } else { CoverCheck::Mismatch };
+    Some(BoundSecret {
+        secret: SecretString::from_utf8(plaintext)?,
+        cover,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn hide_find_secret_col_bound_ut() {
+        let secret = String::from("Lorem ipsum");
+        let mut pixels: Vec<Color> = (0..300)
+            .map(|idx| Color {
+                r: idx as u8,
+                g: (idx * 7) as u8,
+                b: (idx * 13) as u8,
+                a: 255,
+            })
+            .collect();
+
+        // Pixels with no secret return None
+        assert_eq!(None, find_secret_col_bound(&pixels, SecretString::from("password")));
+
+        // The secret is found in its own cover
+        assert!(hide_secret_col_bound(&mut pixels, &secret, SecretString::from("password")).is_ok());
+        let expected = BoundSecret {
+            secret: SecretString::from(secret.to_owned()),
+            cover: CoverCheck::Match,
+        };
+        assert_eq!(Some(expected), find_secret_col_bound(&pixels, SecretString::from("password")));
+        assert_eq!(None, find_secret_col_bound(&pixels, SecretString::from("wrong")));
+
+        // Every secret is sealed with a key derived from its own random salt
+        let salt = |pixels: &[Color]| extract(pixels)[MAGIC.len() + HASH_LEN..][..SALT_LEN].to_vec();
+        let first_salt = salt(&pixels);
+        assert!(hide_secret_col_bound(&mut pixels, &secret, SecretString::from("password")).is_ok());
+        assert_ne!(first_salt, salt(&pixels));
+        assert_eq!(CoverCheck::Match, find_secret_col_bound(&pixels, SecretString::from("password")).unwrap().cover);
+
+        // Copying the least significant bits onto another cover reports a mismatch
+        let other: Vec<Color> = pixels.iter().map(|p| Color { g: p.g ^ 4, ..*p }).collect();
+        assert_eq!(CoverCheck::Mismatch, find_secret_col_bound(&other, SecretString::from("password")).unwrap().cover);
+
+        // Replacing the stored hash without the password is detected
+        let mut forged = other.clone();
+        let hash = cover_hash(forged.as_slice());
+        let mut payload = extract(forged.as_slice());
+        payload[MAGIC.len()..MAGIC.len() + HASH_LEN].copy_from_slice(&hash);
+        embed(forged.as_mut_slice(), &payload).unwrap();
+        assert_eq!(None, find_secret_col_bound(&forged, SecretString::from("password")));
+
+        // Empty secrets or passwords and too small pixel vectors return Error
+        assert!(hide_secret_col_bound(&mut pixels, "", SecretString::from("password")).is_err());
+        assert!(hide_secret_col_bound(&mut pixels, &secret, SecretString::from("")).is_err());
+        assert!(hide_secret_col_bound(&mut pixels[..50], &secret, SecretString::from("password")).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_secret_img_bound_ut() {
+        let path = std::env::temp_dir().join("hips_bound_ut.png");
+        let path = path.to_str().unwrap();
+
+        hide_secret_img_bound("test_images/peppers.png", "Lorem ipsum", SecretString::from("password"))
+            .unwrap()
+            .save(path)
+            .unwrap();
+        let expected = BoundSecret {
+            secret: SecretString::from("Lorem ipsum"),
+            cover: CoverCheck::Match,
+        };
+        assert_eq!(Ok(Some(expected)), find_secret_img_bound(path, SecretString::from("password")));
+        assert_eq!(Ok(None), find_secret_img_bound("test_images/peppers.png", SecretString::from("password")));
+        std::fs::remove_file(path).unwrap();
+
+        assert!(hide_secret_img_bound("test_images/non_existent_image", "Lorem ipsum", SecretString::from("password")).is_err());
+        assert!(find_secret_img_bound("test_images/non_existent_image", SecretString::from("password")).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use std::ops::Range;
+
+use crate::bit_ops::{BitOps, BitOrder, BitReader, BitWriter};
+use crate::color::Color;
+
+#[cfg(feature = "image")]
+use image::{DynamicImage, GenericImage, GenericImageView};
+
+/// Number of carrier samples needed to hide a single byte: eight data bits followed by one termination flag.
+pub const SAMPLES_PER_BYTE: usize = 9;
+
+/// A medium consisting of 8 bit samples whose least significant bits can carry hidden data.
+///
+pub trait Carrier {
+    /// Returns the number of samples available in the carrier.
+    ///
+    fn sample_count(&self) -> usize;
+
+    /// Returns the sample on the specified position.
+    ///
+    fn sample(&self, idx: usize) -> u8;
+
+    /// Replaces the sample on the specified position.
+    ///
+    fn set_sample(&mut self, idx: usize, value: u8);
+}
+
+impl Carrier for [u8] {
+    fn sample_count(&self) -> usize {
+        self.len()
+    }
+
+    fn sample(&self, idx: usize) -> u8 {
+        self[idx]
+    }
+
+    fn set_sample(&mut self, idx: usize, value: u8) {
+        self[idx] = value;
+    }
+}
+
+/// Mutable references to carriers are carriers as well, e.g. to pass a slice of pixel vectors.
+impl<C: Carrier + ?Sized> Carrier for &mut C {
+    fn sample_count(&self) -> usize {
+        (**self).sample_count()
+    }
+
+    fn sample(&self, idx: usize) -> u8 {
+        (**self).sample(idx)
+    }
+
+    fn set_sample(&mut self, idx: usize, value: u8) {
+        (**self).set_sample(idx, value)
+    }
+}
+
+/// Pixels carry three samples each: the R, G and B values. The alpha value is never touched.
+impl Carrier for [Color] {
+    fn sample_count(&self) -> usize {
+        self.len() * 3
+    }
+
+    fn sample(&self, idx: usize) -> u8 {
+        let pixel = &self[idx / 3];
+        match idx % 3 {
+            0 => pixel.r,
+            1 => pixel.g,
+            _ => pixel.b,
+        }
+    }
+
+    fn set_sample(&mut self, idx: usize, value: u8) {
+        let pixel = &mut self[idx / 3];
+        match idx % 3 {
+            0 => pixel.r = value,
+            1 => pixel.g = value,
+            _ => pixel.b = value,
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+/// Images are traversed row by row, every pixel carries three samples: the R, G and B values.
+impl Carrier for DynamicImage {
+    fn sample_count(&self) -> usize {
+        (self.width() as usize) * (self.height() as usize) * 3
+    }
+
+    fn sample(&self, idx: usize) -> u8 {
+        let n = (idx / 3) as u32;
+        self.get_pixel(n % self.width(), n / self.width())[idx % 3]
+    }
+
+    fn set_sample(&mut self, idx: usize, value: u8) {
+        let n = (idx / 3) as u32;
+        let (x, y) = (n % self.width(), n / self.width());
+        let mut pixel = self.get_pixel(x, y);
+        pixel[idx % 3] = value;
+        self.put_pixel(x, y, pixel);
+    }
+}
+
+/// Returns the number of bytes which can be hidden in a carrier with the given number of samples.
+///
+/// # Arguments
+///
+/// * `sample_count` - Number of samples in the carrier.
+///
+pub fn capacity(sample_count: usize) -> usize {
+    sample_count / SAMPLES_PER_BYTE
+}
+
+/// Hides a byte sequence in the least significant bits of the carrier samples.
+/// Every byte occupies nine consecutive samples: eight data bits (least significant bit first)
+/// followed by a flag which is odd if more bytes follow and even for the last byte.
+///
+/// Returns the number of samples which were written.
+///
+/// # Arguments
+///
+/// * `carrier` - Carrier the bytes will be hidden in.
+/// * `bytes` - Bytes which will be hidden in the carrier.
+///
+pub fn embed<C: Carrier + ?Sized>(carrier: &mut C, bytes: &[u8]) -> Result<usize, String> {
+    if bytes.is_empty() {
+        return Err(String::from("There is nothing to hide. Try to provide at least one byte."));
+    }
+
+    if capacity(carrier.sample_count()) < bytes.len() {
+        return Err(String::from("The data is too long to be hidden in this carrier. Try using less data or a larger carrier."));
+    }
+
+    Ok(Embedder::new(bytes).embed_into(carrier))
+}
+
+/// Returns the byte sequence hidden in the least significant bits of the carrier samples.
+/// Reading stops at the first even termination flag or when the carrier runs out of samples.
+///
+/// # Arguments
+///
+/// * `carrier` - Carrier which will be searched for hidden bytes.
+///
+pub fn extract<C: Carrier + ?Sized>(carrier: &C) -> Vec<u8> {
+    let mut extractor = Extractor::new();
+    extractor.extract_from(carrier);
+    extractor.into_bytes()
+}
+
+/// Overwrites the least significant bits of the samples behind a hidden payload with random bits,
+/// so the end of the payload cannot be told apart from the unused samples. Extraction is not affected
+/// as it stops at the termination flag of the last byte.
+///
+/// Returns the number of samples which were overwritten.
+///
+/// # Arguments
+///
+/// * `carrier` - Carrier whose unused samples will be overwritten.
+/// * `start` - Position of the first unused sample, e.g. the result of [`embed`].
+/// * `fraction` - Fraction of the unused samples which will be overwritten, `1.0` for all of them.
+///
+pub fn fill_random<C: Carrier + ?Sized>(carrier: &mut C, start: usize, fraction: f64) -> Result<usize, String> {
+    let end = carrier.sample_count();
+    fill_random_range(carrier, start..end, fraction)
+}
+
+/// Overwrites the least significant bits of the samples in a range like [`fill_random`].
+/// Returns the number of samples which were overwritten.
+///
+/// # Arguments
+///
+/// * `carrier` - Carrier whose samples will be overwritten.
+/// * `range` - Positions of the samples which may be overwritten, limited to the samples of the carrier.
+/// * `fraction` - Fraction of the samples in the range which will be overwritten, `1.0` for all of them.
+///
+pub(crate) fn fill_random_range<C: Carrier + ?Sized>(carrier: &mut C, range: Range<usize>, fraction: f64) -> Result<usize, String> {
+    check_fraction(fraction)?;
+    let end = range.end.min(carrier.sample_count());
+
+    // Every sample consumes a random u32: the lowest bit is the new least significant bit,
+    // the remaining bits decide whether the sample is overwritten at all
+    let threshold = (fraction * (1u64 << 31) as f64) as u64;
+    let mut random = vec![0u8; 4096];
+    let mut written = 0;
+
+    for chunk_start in (range.start..end).step_by(random.len() / 4) {
+        getrandom::getrandom(&mut random).map_err(|err| format!("Failed generating random bits: {err}"))?;
+
+        let chunk_end = (chunk_start + random.len() / 4).min(end);
+        for (idx, bytes) in (chunk_start..chunk_end).zip(random.chunks_exact(4)) {
+            let value = u32::from_le_bytes(bytes.try_into().unwrap());
+            if ((value >> 1) as u64) < threshold {
+                carrier.set_sample(idx, carrier.sample(idx).set_lsb(value & 1 == 1));
+                written += 1;
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+/// Returns Error if the fraction of samples to fill is not between zero and one.
+pub(crate) fn check_fraction(fraction: f64) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!("The fill fraction {fraction} is invalid. Try using a fraction between 0 and 1."));
+    }
+
+    Ok(())
+}
+
+/// Hides a byte sequence sample by sample, possibly spread over several consecutive carriers.
+/// Uses the same layout as [`embed`].
+pub struct Embedder<'a> {
+    bits: BitReader<'a>,
+    /// Number of bytes which are hidden.
+    len: usize,
+    /// Index of the next sample in the stream of all samples needed for the bytes.
+    position: usize,
+}
+
+impl<'a> Embedder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Embedder {
+            bits: BitReader::new(bytes, BitOrder::LsbFirst),
+            len: bytes.len(),
+            position: 0,
+        }
+    }
+
+    /// Returns whether all bytes have been hidden.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.len * SAMPLES_PER_BYTE
+    }
+
+    /// Continues hiding the bytes at the start of the carrier until the bytes or the carrier run out.
+    /// Returns the number of samples which were written.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier the next bits will be hidden in.
+    ///
+    pub fn embed_into<C: Carrier + ?Sized>(&mut self, carrier: &mut C) -> usize {
+        let mut idx = 0;
+
+        while idx < carrier.sample_count() && !self.is_finished() {
+            let bit = match self.position % SAMPLES_PER_BYTE {
+                // The termination flag is even for the last byte and odd otherwise.
+                8 => self.position / SAMPLES_PER_BYTE + 1 < self.len,
+                _ => self.bits.read_bit().unwrap_or(false),
+            };
+
+            carrier.set_sample(idx, carrier.sample(idx).set_lsb(bit));
+            self.position += 1;
+            idx += 1;
+        }
+
+        idx
+    }
+}
+
+/// Retrieves a hidden byte sequence sample by sample, possibly spread over several consecutive carriers.
+/// Uses the same layout as [`extract`].
+#[derive(Default)]
+pub struct Extractor {
+    bits: BitWriter,
+    /// Number of bytes whose termination flag has been read.
+    len: usize,
+    /// Number of samples already read for the current byte.
+    sample_idx: usize,
+    finished: bool,
+}
+
+impl Extractor {
+    pub fn new() -> Self {
+        Extractor::default()
+    }
+
+    /// Returns whether the termination flag of the last byte has been read.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Continues reading bytes from the start of the carrier until the last byte or the end of the carrier is reached.
+    /// Returns the number of samples which were read.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier the next bits will be read from.
+    ///
+    pub fn extract_from<C: Carrier + ?Sized>(&mut self, carrier: &C) -> usize {
+        let mut idx = 0;
+
+        while idx < carrier.sample_count() && !self.finished {
+            let bit = carrier.sample(idx).get_lsb();
+
+            if self.sample_idx < 8 {
+                self.bits.write_bit(bit);
+                self.sample_idx += 1;
+            } else {
+                self.len += 1;
+                self.sample_idx = 0;
+                // An even termination flag marks the last byte.
+                self.finished = !bit;
+            }
+
+            idx += 1;
+        }
+
+        idx
+    }
+
+    /// Returns all complete bytes read so far.
+    pub fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = self.bits.into_bytes();
+        bytes.truncate(self.len);
+        bytes
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn embed_extract_ut() {
+        let mut samples = vec![0u8; 27];
+
+        // Samples without hidden data return a single zero byte
+        assert_eq!(vec![0], extract(samples.as_slice()));
+
+        // Successfully embed and extract bytes
+        let bytes = vec![0, 255, 42];
+        assert_eq!(Ok(27), embed(samples.as_mut_slice(), &bytes));
+        assert_eq!(bytes, extract(samples.as_slice()));
+
+        // Only the least significant bits are touched
+        assert!(samples.iter().all(|s| *s <= 1));
+
+        // Bytes which do not fit in the carrier return Error
+        assert!(embed(samples.as_mut_slice(), &[1, 2, 3, 4]).is_err());
+
+        // Empty data returns Error
+        assert!(embed(samples.as_mut_slice(), &[]).is_err());
+
+        // Carriers which are too small return an empty result
+        assert!(extract(&samples[..8]).is_empty());
+    }
+
+    #[test]
+    fn embedder_extractor_ut() {
+        let bytes = vec![1, 2, 3, 4, 5];
+        let mut first = vec![0u8; 20];
+        let mut second = vec![0u8; 20];
+        let mut third = vec![0u8; 20];
+
+        // Bytes are continued across carriers
+        let mut embedder = Embedder::new(&bytes);
+        assert_eq!(20, embedder.embed_into(first.as_mut_slice()));
+        assert!(!embedder.is_finished());
+        assert_eq!(20, embedder.embed_into(second.as_mut_slice()));
+        assert_eq!(5, embedder.embed_into(third.as_mut_slice()));
+        assert!(embedder.is_finished());
+        assert_eq!(0, embedder.embed_into(third.as_mut_slice()));
+
+        let mut extractor = Extractor::new();
+        assert_eq!(20, extractor.extract_from(first.as_slice()));
+        assert_eq!(20, extractor.extract_from(second.as_slice()));
+        assert!(!extractor.is_finished());
+        assert_eq!(5, extractor.extract_from(third.as_slice()));
+        assert!(extractor.is_finished());
+        assert_eq!(bytes, extractor.into_bytes());
+
+        // The result matches a single carrier
+        let joined = [first, second, third].concat();
+        assert_eq!(bytes, extract(joined.as_slice()));
+    }
+
+    #[test]
+    fn fill_random_ut() {
+        let mut samples = vec![0u8; 9000];
+        let bytes = vec![1, 2, 3];
+        let written = embed(samples.as_mut_slice(), &bytes).unwrap();
+
+        // All unused samples are overwritten and the bytes are preserved
+        assert_eq!(Ok(9000 - written), fill_random(samples.as_mut_slice(), written, 1.0));
+        assert_eq!(bytes, extract(samples.as_slice()));
+        let ones = samples[written..].iter().filter(|s| s.get_lsb()).count();
+        assert!(ones > 4000 && ones < 5000);
+
+        // Only about half of the unused samples are overwritten
+        let mut samples = vec![0u8; 9000];
+        let written = fill_random(samples.as_mut_slice(), 0, 0.5).unwrap();
+        assert!(written > 4000 && written < 5000);
+        assert!(samples.iter().all(|s| *s <= 1));
+
+        assert_eq!(Ok(0), fill_random(samples.as_mut_slice(), 0, 0.0));
+        assert_eq!(Ok(0), fill_random(samples.as_mut_slice(), 9000, 1.0));
+
+        // Invalid fractions return Error
+        assert!(fill_random(samples.as_mut_slice(), 0, 1.5).is_err());
+        assert!(fill_random(samples.as_mut_slice(), 0, -0.1).is_err());
+    }
+
+    #[test]
+    fn color_carrier_ut() {
+        let mut pixels = vec![Color::from_rgba(10, 20, 30, 40); 3];
+        assert_eq!(9, pixels.sample_count());
+
+        pixels.set_sample(4, 21);
+        assert_eq!(21, pixels.sample(4));
+        assert_eq!(21, pixels[1].g);
+
+        // Alpha values are preserved
+        assert!(embed(pixels.as_mut_slice(), &[255]).is_ok());
+        assert!(pixels.iter().all(|p| p.a == 40));
+        assert_eq!(vec![255], extract(pixels.as_slice()));
+    }
+}
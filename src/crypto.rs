@@ -0,0 +1,300 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::carrier::Carrier;
+
+/// Number of random bytes in front of every sealed payload.
+pub(crate) const NONCE_LEN: usize = 12;
+
+/// Number of bytes of the authentication tag behind every sealed payload.
+pub(crate) const TAG_LEN: usize = 16;
+
+/// Number of random bytes of the salt stored with every payload encrypted with a password.
+pub(crate) const SALT_LEN: usize = 16;
+
+/// Number of PBKDF2 iterations used to derive a key from a password, as recommended by OWASP for PBKDF2-HMAC-SHA256.
+const KDF_ROUNDS: u32 = 600_000;
+
+/// Returns a 256 bit key derived from a password. The context separates keys used for different purposes,
+/// the salt separates keys derived from the same password for different payloads.
+///
+/// # Arguments
+///
+/// * `password` - Password the key is derived from.
+/// * `context` - Fixed string naming the purpose of the key.
+/// * `salt` - Random bytes stored next to the payload, empty if the key must be known before anything is read.
+///
+pub(crate) fn derive_key(password: &str, context: &str, salt: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &[context.as_bytes(), salt].concat(), KDF_ROUNDS, key.as_mut());
+    key
+}
+
+/// Returns random bytes from the operating system.
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).map_err(|err| format!("Failed generating random bytes: {err}"))?;
+    Ok(bytes)
+}
+
+/// Returns a random 256 bit key from the operating system, wiped when it is dropped.
+pub(crate) fn random_key() -> Result<Zeroizing<[u8; 32]>, String> {
+    let mut key = Zeroizing::new([0; 32]);
+    getrandom::getrandom(key.as_mut()).map_err(|err| format!("Failed generating random bytes: {err}"))?;
+    Ok(key)
+}
+
+/// Encrypts and authenticates bytes with ChaCha20-Poly1305. Returns the random nonce followed by the ciphertext and tag.
+///
+/// # Arguments
+///
+/// * `key` - Key the bytes will be encrypted with.
+/// * `plaintext` - Bytes which will be encrypted.
+///
+pub(crate) fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    seal_aad(key, plaintext, &[])
+}
+
+/// Encrypts bytes like [`seal`] and authenticates additional data which is not part of the payload,
+/// so the payload can only be opened together with the same data.
+///
+/// # Arguments
+///
+/// * `key` - Key the bytes will be encrypted with.
+/// * `plaintext` - Bytes which will be encrypted.
+/// * `aad` - Additional data which will be authenticated but not encrypted.
+///
+pub(crate) fn seal_aad(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    let mut sealed = random_bytes(NONCE_LEN)?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&sealed), Payload { msg: plaintext, aad })
+        .map_err(|_| String::from("Failed encrypting the secret."))?;
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Returns the decrypted bytes of a payload created by [`seal`], or `None` if the key is wrong or the payload was modified.
+///
+/// # Arguments
+///
+/// * `key` - Key the bytes were encrypted with.
+/// * `sealed` - Nonce, ciphertext and tag.
+///
+pub(crate) fn open(key: &[u8; 32], sealed: &[u8]) -> Option<Vec<u8>> {
+    open_aad(key, sealed, &[])
+}
+
+/// Returns the decrypted bytes of a payload created by [`seal_aad`], or `None` if the key or the additional data
+/// is wrong or the payload was modified.
+///
+/// # Arguments
+///
+/// * `key` - Key the bytes were encrypted with.
+/// * `sealed` - Nonce, ciphertext and tag.
+/// * `aad` - Additional data the payload was sealed with.
+///
+pub(crate) fn open_aad(key: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return None;
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(key)).decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad }).ok()
+}
+
+/// Returns a hash over the samples of a carrier with their least significant bits cleared.
+/// Embedding never changes these bits, so the hash of a cover equals the hash of the carrier created from it.
+///
+/// # Arguments
+///
+/// * `carrier` - Carrier which will be hashed.
+///
+pub(crate) fn cover_hash<C: Carrier + ?Sized>(carrier: &C) -> [u8; 32] {
+    let mut hasher = Sha256::new().chain_update((carrier.sample_count() as u64).to_le_bytes());
+    let mut buffer = Vec::with_capacity(4096);
+
+    for idx in 0..carrier.sample_count() {
+        buffer.push(carrier.sample(idx) & !1);
+        if buffer.len() == buffer.capacity() {
+            hasher.update(&buffer);
+            buffer.clear();
+        }
+    }
+    hasher.update(&buffer);
+
+    hasher.finalize().into()
+}
+
+/// Returns an endless stream of pseudo random numbers determined by the key and a label.
+/// The stream is SHA-256 in counter mode.
+///
+/// # Arguments
+///
+/// * `key` - Key the stream is derived from.
+/// * `label` - Fixed string separating streams derived from the same key.
+///
+pub(crate) fn keystream<'a>(key: &'a [u8; 32], label: &'a str) -> impl Iterator<Item = u64> + 'a {
+    (0u64..).flat_map(move |counter| {
+        let block = Sha256::new().chain_update(key).chain_update(label).chain_update(counter.to_le_bytes()).finalize();
+        let numbers: Vec<u64> = block.chunks_exact(8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())).collect();
+        numbers
+    })
+}
+
+/// Number of Feistel rounds of a [`Permutation`].
+const PERMUTATION_ROUNDS: u8 = 4;
+
+/// A key dependent permutation of the positions below `len` which is evaluated lazily, so the memory
+/// needed does not depend on the number of positions.
+///
+/// Positions are encrypted with a balanced Feistel network over the smallest even number of bits covering `len`,
+/// the rounds being keyed SHA-256. Results which are not below `len` are encrypted again until they are (cycle walking).
+pub(crate) struct Permutation {
+    key: Zeroizing<[u8; 32]>,
+    label: String,
+    len: usize,
+    half_bits: u32,
+}
+
+impl Permutation {
+    /// Returns the permutation of the positions below `len` determined by the key and a label.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key the order is derived from.
+    /// * `label` - Fixed string separating orders derived from the same key.
+    /// * `len` - Number of available positions.
+    ///
+    pub(crate) fn new(key: &[u8; 32], label: &str, len: usize) -> Self {
+        let bits = usize::BITS - len.saturating_sub(1).leading_zeros();
+        Permutation {
+            key: Zeroizing::new(*key),
+            label: String::from(label),
+            len,
+            half_bits: bits.div_ceil(2).max(1),
+        }
+    }
+
+    /// Returns the position the n-th position is moved to, `n` has to be below the number of positions.
+    pub(crate) fn get(&self, n: usize) -> usize {
+        let mut value = n as u64;
+        loop {
+            value = self.encrypt(value);
+            if value < self.len as u64 {
+                return value as usize;
+            }
+        }
+    }
+
+    fn encrypt(&self, value: u64) -> u64 {
+        let mask = (1 << self.half_bits) - 1;
+        let (mut left, mut right) = (value >> self.half_bits, value & mask);
+
+        for round in 0..PERMUTATION_ROUNDS {
+            let block = Sha256::new()
+                .chain_update(*self.key)
+                .chain_update(&self.label)
+                .chain_update([round])
+                .chain_update(right.to_le_bytes())
+                .finalize();
+            let mixed = u64::from_le_bytes(block[..8].try_into().unwrap()) & mask;
+            (left, right) = (right, left ^ mixed);
+        }
+
+        (left << self.half_bits) | right
+    }
+}
+
+/// Returns `count` distinct positions below `len` in a key dependent order.
+/// The positions are the start of a [`Permutation`], so a larger count continues the same order.
+///
+/// # Arguments
+///
+/// * `key` - Key the order is derived from.
+/// * `label` - Fixed string separating orders derived from the same key.
+/// * `len` - Number of available positions.
+/// * `count` - Number of positions which will be returned, at most `len`.
+///
+pub(crate) fn select_positions(key: &[u8; 32], label: &str, len: usize, count: usize) -> impl Iterator<Item = usize> {
+    let permutation = Permutation::new(key, label, len);
+    (0..count.min(len)).map(move |n| permutation.get(n))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_open_ut() {
+        let key = derive_key("password", "test", b"salt");
+        assert_ne!(key, derive_key("password", "other", b"salt"));
+        assert_ne!(key, derive_key("password", "test", b"pepper"));
+        assert_ne!(key, derive_key("Password", "test", b"salt"));
+
+        let sealed = seal(&key, b"Lorem ipsum").unwrap();
+        assert_eq!(NONCE_LEN + 11 + TAG_LEN, sealed.len());
+        assert_eq!(Some(b"Lorem ipsum".to_vec()), open(&key, &sealed));
+
+        // Random nonces produce different payloads for the same plaintext
+        assert_ne!(sealed, seal(&key, b"Lorem ipsum").unwrap());
+
+        // Wrong keys, modified or truncated payloads are rejected
+        assert_eq!(None, open(&derive_key("wrong", "test", b"salt"), &sealed));
+        let mut modified = sealed.clone();
+        modified[NONCE_LEN] ^= 1;
+        assert_eq!(None, open(&key, &modified));
+        assert_eq!(None, open(&key, &sealed[..NONCE_LEN]));
+
+        // Additional data has to match as well
+        let sealed = seal_aad(&key, b"Lorem ipsum", b"cover").unwrap();
+        assert_eq!(Some(b"Lorem ipsum".to_vec()), open_aad(&key, &sealed, b"cover"));
+        assert_eq!(None, open_aad(&key, &sealed, b"other"));
+        assert_eq!(None, open(&key, &sealed));
+    }
+
+    #[test]
+    fn cover_hash_ut() {
+        let mut samples = [10u8, 20, 30, 40];
+        let hash = cover_hash(samples.as_slice());
+
+        // Least significant bits do not change the hash, all other bits and the length do
+        samples[1] = 21;
+        assert_eq!(hash, cover_hash(samples.as_slice()));
+        samples[2] = 32;
+        assert_ne!(hash, cover_hash(samples.as_slice()));
+        assert_ne!(hash, cover_hash(&samples[..3]));
+    }
+
+    #[test]
+    fn select_positions_ut() {
+        let key = derive_key("password", "test", &[]);
+
+        // Positions are distinct and a larger count continues the same order
+        let positions: Vec<usize> = select_positions(&key, "lane", 100, 40).collect();
+        assert_eq!(40, positions.len());
+        assert!(positions.iter().all(|p| *p < 100));
+        let mut sorted = positions.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(40, sorted.len());
+        assert_eq!(positions[..], select_positions(&key, "lane", 100, 60).collect::<Vec<usize>>()[..40]);
+
+        // Different labels produce different orders, counts are limited to the available positions
+        assert_ne!(positions, select_positions(&key, "other", 100, 40).collect::<Vec<usize>>());
+        assert_eq!(100, select_positions(&key, "lane", 100, 200).count());
+
+        // All positions are visited exactly once, whatever the number of positions
+        for len in [1, 2, 3, 5, 16, 17, 1000] {
+            let mut sorted: Vec<usize> = select_positions(&key, "lane", len, len).collect();
+            sorted.sort();
+            assert_eq!((0..len).collect::<Vec<usize>>(), sorted);
+        }
+
+        // The order is evaluated lazily, so huge carriers need no memory
+        let positions: Vec<usize> = select_positions(&key, "lane", usize::MAX, 10).collect();
+        assert_eq!(10, positions.len());
+        assert!(positions.iter().any(|p| *p > u32::MAX as usize));
+    }
+}
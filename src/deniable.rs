@@ -0,0 +1,312 @@
+use crate::bit_ops::{BitOps, BitOrder, BitReader, BitWriter};
+use crate::carrier::{fill_random, Carrier};
+use crate::color::Color;
+use crate::crypto::{derive_key, keystream, open, random_bytes, random_key, seal, select_positions, NONCE_LEN, SALT_LEN, TAG_LEN};
+use crate::secret::SecretString;
+use zeroize::Zeroizing;
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// Context of the keys derived from the passwords.
+const KEY_CONTEXT: &str = "hips-lib deniable";
+
+/// Labels of the two lanes, the even and the odd samples of the carrier.
+const LANES: [&str; 2] = ["lane 0", "lane 1"];
+
+/// Labels of the masks hiding the length of the secret in each lane.
+const LENGTH_MASKS: [&str; 2] = ["length 0", "length 1"];
+
+/// Number of bytes in front of the sealed secret: its length as masked little endian `u32`.
+const LENGTH_LEN: usize = 4;
+
+/// Number of samples at the start of every lane holding the salt of the key, in order.
+const SALT_BITS: usize = SALT_LEN * 8;
+
+/// The salt, the key derived with it and the sealed secret written to one lane.
+type LanePayload = (Vec<u8>, Zeroizing<[u8; 32]>, Vec<u8>);
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and hide a decoy and a real secret in it, see [`hide_deniable_col`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `decoy` - Harmless secret which can be revealed under pressure.
+/// * `decoy_password` - Password unlocking the decoy.
+/// * `secret` - Real secret.
+/// * `password` - Password unlocking the real secret.
+///
+pub fn hide_deniable_img(img_path: &str, decoy: &str, decoy_password: SecretString, secret: &str, password: SecretString) -> Result<DynamicImage, String> {
+    let mut img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    hide_deniable(&mut img, [(decoy, &decoy_password), (secret, &password)])?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and hide a single secret in it, see [`hide_deniable_single_col`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the image.
+/// * `password` - Password unlocking the secret.
+///
+pub fn hide_deniable_single_img(img_path: &str, secret: &str, password: SecretString) -> Result<DynamicImage, String> {
+    let mut img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    hide_deniable_single(&mut img, secret, &password)?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and searches it for a secret unlocked by the password, see [`find_deniable_col`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for hidden secrets.
+/// * `password` - Either the decoy or the real password.
+///
+pub fn find_deniable_img(img_path: &str, password: SecretString) -> Result<Option<SecretString>, String> {
+    let img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    Ok(find_deniable(&img, &password))
+}
+
+/// Hides a decoy and a real secret in a vector of pixels, each unlocked by its own password.
+///
+/// The least significant bits are split into two lanes, the secrets are assigned to the lanes at random.
+/// Every secret is encrypted and written to positions chosen by its password, all remaining bits are
+/// filled with random bits. Without a password both lanes look like noise, so revealing the decoy
+/// password does not reveal that a second secret exists.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secrets will be hidden in.
+/// * `decoy` - Harmless secret which can be revealed under pressure.
+/// * `decoy_password` - Password unlocking the decoy.
+/// * `secret` - Real secret.
+/// * `password` - Password unlocking the real secret.
+///
+pub fn hide_deniable_col(pixels: &mut [Color], decoy: &str, decoy_password: SecretString, secret: &str, password: SecretString) -> Result<(), String> {
+    hide_deniable(pixels, [(decoy, &decoy_password), (secret, &password)])
+}
+
+/// Hides a single secret in a vector of pixels, laid out exactly like [`hide_deniable_col`].
+///
+/// The second lane receives random bytes sealed with a random key, so the carrier can not be told apart
+/// from one holding a decoy and a real secret. Nobody can unlock the second lane, which allows to claim
+/// that it holds a secret whose password has been forgotten.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secret will be hidden in.
+/// * `secret` - Secret text which will be hidden in the pixels.
+/// * `password` - Password unlocking the secret.
+///
+pub fn hide_deniable_single_col(pixels: &mut [Color], secret: &str, password: SecretString) -> Result<(), String> {
+    hide_deniable_single(pixels, secret, &password)
+}
+
+/// Returns the secret unlocked by the password from a vector of pixels, either the decoy or the real secret.
+/// Returns `None` if neither secret belongs to the password.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels which will be searched for a secret.
+/// * `password` - Either the decoy or the real password.
+///
+pub fn find_deniable_col(pixels: &[Color], password: SecretString) -> Option<SecretString> {
+    find_deniable(pixels, &password)
+}
+
+fn hide_deniable<C: Carrier + ?Sized>(carrier: &mut C, secrets: [(&str, &str); 2]) -> Result<(), String> {
+    if secrets.iter().any(|(secret, _)| secret.is_empty()) {
+        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
+    }
+    if secrets.iter().any(|(_, password)| password.is_empty()) {
+        return Err(String::from("You have entered an empty password. Try to use at least one character in both passwords."));
+    }
+    if secrets[0].1 == secrets[1].1 {
+        return Err(String::from("The decoy password and the real password are equal. Try using two different passwords."));
+    }
+
+    let mut payloads = vec![];
+    for (secret, password) in secrets {
+        let salt = random_bytes(SALT_LEN)?;
+        let key = derive_key(password, KEY_CONTEXT, &salt);
+        let sealed = seal(&key, secret.as_bytes())?;
+        payloads.push((salt, key, sealed));
+    }
+
+    write_lanes(carrier, payloads)
+}
+
+fn hide_deniable_single<C: Carrier + ?Sized>(carrier: &mut C, secret: &str, password: &str) -> Result<(), String> {
+    if secret.is_empty() {
+        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
+    }
+    if password.is_empty() {
+        return Err(String::from("You have entered an empty password. Try to use at least one character in the password."));
+    }
+
+    let salt = random_bytes(SALT_LEN)?;
+    let key = derive_key(password, KEY_CONTEXT, &salt);
+    let sealed = seal(&key, secret.as_bytes())?;
+
+    // A dummy of the same length sealed with a key nobody knows
+    let dummy_key = random_key()?;
+    let dummy = seal(&dummy_key, &random_bytes(secret.len())?)?;
+
+    write_lanes(carrier, vec![(salt, key, sealed), (random_bytes(SALT_LEN)?, dummy_key, dummy)])
+}
+
+/// Writes two sealed payloads to the lanes of the carrier. The salt of each key is written to the start of its lane,
+/// the sealed payload to the positions chosen by the key.
+fn write_lanes<C: Carrier + ?Sized>(carrier: &mut C, payloads: Vec<LanePayload>) -> Result<(), String> {
+    let sample_count = carrier.sample_count();
+
+    // Either secret may end up in the smaller odd lane
+    if payloads.iter().any(|(_, _, sealed)| SALT_BITS + (LENGTH_LEN + sealed.len()) * 8 > lane_len(sample_count, 1)) {
+        return Err(String::from("The secrets are too long to be hidden in this carrier. Try using shorter secrets or a larger carrier."));
+    }
+
+    // Neither the lane of the decoy nor the values of unused bits may depend on the secrets
+    fill_random(carrier, 0, 1.0)?;
+    let first_lane = (random_bytes(1)?[0] & 1) as usize;
+
+    for (idx, (salt, key, sealed)) in payloads.iter().enumerate() {
+        let lane = (first_lane + idx) % 2;
+        let mut payload = ((sealed.len() as u32) ^ length_mask(key, lane)).to_le_bytes().to_vec();
+        payload.extend(sealed);
+
+        write_lane(carrier, lane, 0..SALT_BITS, salt);
+        write_lane(carrier, lane, payload_positions(key, lane, sample_count, payload.len()), &payload);
+    }
+
+    Ok(())
+}
+
+fn find_deniable<C: Carrier + ?Sized>(carrier: &C, password: &str) -> Option<SecretString> {
+    let sample_count = carrier.sample_count();
+
+    (0..LANES.len()).find_map(|lane| {
+        if lane_len(sample_count, lane) < SALT_BITS + LENGTH_LEN * 8 {
+            return None;
+        }
+
+        let key = derive_key(password, KEY_CONTEXT, &read_lane(carrier, lane, 0..SALT_BITS));
+
+        let length = read_lane(carrier, lane, payload_positions(&key, lane, sample_count, LENGTH_LEN));
+        let length = (u32::from_le_bytes(length.try_into().unwrap()) ^ length_mask(&key, lane)) as usize;
+        if length < NONCE_LEN + TAG_LEN || SALT_BITS + (LENGTH_LEN + length) * 8 > lane_len(sample_count, lane) {
+            return None;
+        }
+
+        let bytes = read_lane(carrier, lane, payload_positions(&key, lane, sample_count, LENGTH_LEN + length));
+        let plaintext = open(&key, &bytes[LENGTH_LEN..])?;
+        SecretString::from_utf8(plaintext)
+    })
+}
+
+/// Writes bytes to the least significant bits of the given positions in a lane.
+fn write_lane<C: Carrier + ?Sized>(carrier: &mut C, lane: usize, positions: impl Iterator<Item = usize>, bytes: &[u8]) {
+    for (position, bit) in positions.zip(BitReader::new(bytes, BitOrder::LsbFirst)) {
+        let idx = 2 * position + lane;
+        carrier.set_sample(idx, carrier.sample(idx).set_lsb(bit));
+    }
+}
+
+/// Returns the bytes read from the least significant bits of the given positions in a lane.
+fn read_lane<C: Carrier + ?Sized>(carrier: &C, lane: usize, positions: impl Iterator<Item = usize>) -> Vec<u8> {
+    let mut bits = BitWriter::new(BitOrder::LsbFirst);
+    for position in positions {
+        bits.write_bit(carrier.sample(2 * position + lane).get_lsb());
+    }
+    bits.into_bytes()
+}
+
+/// Returns the key dependent positions in a lane of the first `len` bytes behind the salt.
+fn payload_positions(key: &[u8; 32], lane: usize, sample_count: usize, len: usize) -> impl Iterator<Item = usize> {
+    select_positions(key, LANES[lane], lane_len(sample_count, lane) - SALT_BITS, len * 8).map(|position| SALT_BITS + position)
+}
+
+/// Returns the number of samples in a lane.
+fn lane_len(sample_count: usize, lane: usize) -> usize {
+    (sample_count + 1 - lane) / 2
+}
+
+/// Returns the key dependent mask hiding the length of a sealed secret.
+fn length_mask(key: &[u8; 32], lane: usize) -> u32 {
+    keystream(key, LENGTH_MASKS[lane]).next().unwrap() as u32
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn hide_find_deniable_col_ut() {
+        let (decoy, decoy_password) = ("Shopping list", SecretString::from("decoy"));
+        let (secret, password) = ("Lorem ipsum dolor sit amet", SecretString::from("password"));
+
+        // Pixels with no secret return None
+        let mut pixels = vec![Color::new(); 1000];
+        assert_eq!(None, find_deniable_col(&pixels, password.to_owned()));
+
+        // Each password unlocks its own secret only
+        assert!(hide_deniable_col(&mut pixels, decoy, decoy_password.to_owned(), secret, password.to_owned()).is_ok());
+        assert_eq!(Some(SecretString::from(decoy)), find_deniable_col(&pixels, decoy_password.to_owned()));
+        assert_eq!(Some(SecretString::from(secret)), find_deniable_col(&pixels, password.to_owned()));
+        assert_eq!(None, find_deniable_col(&pixels, SecretString::from("wrong")));
+
+        // All least significant bits are randomized, not only the ones carrying the secrets
+        let ones = pixels.iter().flat_map(|p| [p.r, p.g, p.b]).filter(|v| *v == 1).count();
+        assert!(ones > 1200 && ones < 1800);
+
+        // Equal or empty passwords, empty secrets or too small carriers return Error
+        assert!(hide_deniable_col(&mut pixels, decoy, password.to_owned(), secret, password.to_owned()).is_err());
+        assert!(hide_deniable_col(&mut pixels, decoy, SecretString::from(""), secret, password.to_owned()).is_err());
+        assert!(hide_deniable_col(&mut pixels, "", decoy_password.to_owned(), secret, password.to_owned()).is_err());
+        assert!(hide_deniable_col(&mut pixels[..50], decoy, decoy_password.to_owned(), secret, password.to_owned()).is_err());
+    }
+
+    #[test]
+    fn hide_find_deniable_single_col_ut() {
+        let (secret, password) = ("Lorem ipsum dolor sit amet", SecretString::from("password"));
+
+        // The password unlocks the secret, nothing else unlocks the second lane
+        let mut pixels = vec![Color::new(); 1000];
+        assert!(hide_deniable_single_col(&mut pixels, secret, password.to_owned()).is_ok());
+        assert_eq!(Some(SecretString::from(secret)), find_deniable_col(&pixels, password.to_owned()));
+        assert_eq!(None, find_deniable_col(&pixels, SecretString::from("decoy")));
+
+        // Both lanes are filled like with two secrets
+        let ones = pixels.iter().flat_map(|p| [p.r, p.g, p.b]).filter(|v| *v == 1).count();
+        assert!(ones > 1200 && ones < 1800);
+
+        // Empty passwords, empty secrets or too small carriers return Error
+        assert!(hide_deniable_single_col(&mut pixels, secret, SecretString::from("")).is_err());
+        assert!(hide_deniable_single_col(&mut pixels, "", password.to_owned()).is_err());
+        assert!(hide_deniable_single_col(&mut pixels[..50], secret, password.to_owned()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_deniable_img_ut() {
+        let img = hide_deniable_img("test_images/peppers.png", "Decoy", SecretString::from("decoy"), "Lorem ipsum", SecretString::from("password")).unwrap();
+        let path = std::env::temp_dir().join("hips_deniable_ut.png");
+        img.save(&path).unwrap();
+
+        let path = path.to_str().unwrap();
+        assert_eq!(Ok(Some(SecretString::from("Decoy"))), find_deniable_img(path, SecretString::from("decoy")));
+        assert_eq!(Ok(Some(SecretString::from("Lorem ipsum"))), find_deniable_img(path, SecretString::from("password")));
+        assert_eq!(Ok(None), find_deniable_img("test_images/peppers.png", SecretString::from("password")));
+        std::fs::remove_file(path).unwrap();
+
+        let img = hide_deniable_single_img("test_images/peppers.png", "Lorem ipsum", SecretString::from("password")).unwrap();
+        assert_eq!(Some(SecretString::from("Lorem ipsum")), find_deniable(&img, "password"));
+
+        assert!(hide_deniable_img("test_images/non_existent_image", "Decoy", SecretString::from("decoy"), "Lorem ipsum", SecretString::from("password")).is_err());
+        assert!(hide_deniable_single_img("test_images/non_existent_image", "Lorem ipsum", SecretString::from("password")).is_err());
+        assert!(find_deniable_img("test_images/non_existent_image", SecretString::from("password")).is_err());
+    }
+}
@@ -1,11 +1,43 @@
-use crate::bit_ops::{BitBuffer, BitOps};
+use crate::carrier::{check_fraction, fill_random};
 use crate::color::Color;
 use crate::otp::otp;
+use crate::secret::{SecretBytes, SecretString};
+use crate::stego::Stego;
 
 #[cfg(feature = "image")]
-use image::GenericImage;
+use image::{DynamicImage, ImageFormat};
 #[cfg(feature = "image")]
-use image::{DynamicImage, GenericImageView};
+use std::io::{Cursor, Write};
+#[cfg(feature = "image")]
+use std::path::Path;
+
+#[cfg(feature = "image")]
+/// Target of [`save_stego`], either a file or any writer.
+pub enum StegoOutput<'a> {
+    Path(&'a Path),
+    Writer(&'a mut dyn Write),
+}
+
+#[cfg(feature = "image")]
+impl<'a> From<&'a str> for StegoOutput<'a> {
+    fn from(path: &'a str) -> Self {
+        StegoOutput::Path(Path::new(path))
+    }
+}
+
+#[cfg(feature = "image")]
+impl<'a> From<&'a Path> for StegoOutput<'a> {
+    fn from(path: &'a Path) -> Self {
+        StegoOutput::Path(path)
+    }
+}
+
+#[cfg(feature = "image")]
+impl<'a, W: Write> From<&'a mut W> for StegoOutput<'a> {
+    fn from(writer: &'a mut W) -> Self {
+        StegoOutput::Writer(writer)
+    }
+}
 
 #[cfg(feature = "image")]
 /// Tries to load the target image and hide the given secret in it.
@@ -15,10 +47,10 @@ use image::{DynamicImage, GenericImageView};
 /// * `img_path` - Path to the target image file.
 /// * `secret` - Secret text which will be hidden in the image.
 ///
-pub fn hide_secret_img(img_path: &str, secret: &String, password: Option<String>) -> Result<DynamicImage, String> {
+pub fn hide_secret_img(img_path: &str, secret: &str, password: Option<SecretString>) -> Result<DynamicImage, String> {
     if let Ok(mut img) = image::open(img_path) {
         match encode_secret_img(&mut img, secret, password) {
-            Ok(()) => Ok(img),
+            Ok(_) => Ok(img),
             Err(err) => Err(err),
         }
     } else {
@@ -28,62 +60,44 @@ pub fn hide_secret_img(img_path: &str, secret: &String, password: Option<String>
 }
 
 #[cfg(feature = "image")]
-/// Hides a secret string in the target image.
+/// Tries to load the target image, hide the given secret in it and overwrite the least significant bits
+/// behind the secret with random bits, so the length of the secret is not revealed.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the image.
+/// * `fill` - Fraction of the unused values which will be overwritten, `1.0` for all of them.
+///
+pub fn hide_secret_img_filled(img_path: &str, secret: &str, password: Option<SecretString>, fill: f64) -> Result<DynamicImage, String> {
+    check_fraction(fill)?;
+
+    let mut img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    let written = encode_secret_img(&mut img, secret, password)?;
+    fill_random(&mut img, written, fill)?;
+
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Hides a secret string in an image which is already loaded. Returns the number of values which were written.
 ///
 /// # Arguments
 ///
 /// * `img` - Target source image the secret will be written to.
 /// * `secret`  - Secret string which will be hidden in the target image.
 ///
-fn encode_secret_img(img: &mut DynamicImage, secret: &String, password: Option<String>) -> Result<(), String> {
-    if secret.is_empty() {
-        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
-    }
+pub fn encode_secret_img(img: &mut DynamicImage, secret: &str, password: Option<SecretString>) -> Result<usize, String> {
+    let secret_bytes = secret_to_bytes(secret, password)?;
+    let stego = Stego::default();
 
-    if (img.pixels().count()) < secret.len() * 3 {
+    if stego.capacity(img) < secret_bytes.len() {
         return Err(String::from(
             "The message is too long to be hidden in this picture. Try using a shorter message or a larger input image.",
         ));
     }
 
-    let secret_bytes = match password {
-        Some(pwd) => otp(secret, &pwd).into_bytes(),
-        None => secret.to_owned().into_bytes(),
-    };
-
-    for (byte_idx, byte) in secret_bytes.iter().enumerate() {
-        // Index of the first (of three) Pixel
-        let n_rgb = 3 * byte_idx as u32;
-
-        let bit_buffer = byte.to_bit_buffer();
-
-        for (bit_chunk_idx, bit_chunk) in bit_buffer.chunks(3).enumerate() {
-            // Calculate (image) row and column of the current pixel
-            let n: u32 = n_rgb + bit_chunk_idx as u32;
-            let col_idx = n / img.width();
-            let row_idx = n % img.width();
-
-            // Replace the least signbificant bits for R and G values of the Pixel.
-            let o_pixel = img.get_pixel(row_idx, col_idx);
-            let r_out = o_pixel[0].set_lsb(bit_chunk[0]);
-            let g_out = o_pixel[1].set_lsb(bit_chunk[1]);
-
-            let b_out: u8 = if bit_chunk.len() > 2 {
-                // More than 2 bits in chunk, not the last chunk. We expect more chunks to come
-                o_pixel[2].set_lsb(bit_chunk[2])
-            } else if byte_idx + 1 == secret_bytes.len() {
-                // This is the last secret char, we set the last byte to be even.
-                o_pixel[2].set_lsb(false)
-            } else {
-                o_pixel[2].set_lsb(true) // Last chunk but not the last secret char, we set the last byte to be odd.
-            };
-
-            // Write pixel back to image
-            img.put_pixel(row_idx, col_idx, image::Rgba([r_out, g_out, b_out, o_pixel[3]]));
-        }
-    }
-
-    Ok(())
+    stego.hide(img, &secret_bytes)
 }
 
 /// Hides a secret in an vector of pixels.
@@ -93,52 +107,44 @@ fn encode_secret_img(img: &mut DynamicImage, secret: &String, password: Option<S
 /// * `pixels` - Vector of pixels the secret will be hidden in.
 /// * `secret` - The secret string.
 ///
-pub fn hide_secret_col(pixels: &mut Vec<Color>, secret: &String, password: Option<String>) -> Result<(), String> {
-    if secret.is_empty() {
-        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
-    }
+pub fn hide_secret_col(pixels: &mut [Color], secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    encode_secret_col(pixels, secret, password).map(|_| ())
+}
 
-    if pixels.len() < secret.len() * 3 {
+/// Hides a secret in an vector of pixels and overwrites the least significant bits behind the secret
+/// with random bits, so the length of the secret is not revealed.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secret will be hidden in.
+/// * `secret` - The secret string.
+/// * `fill` - Fraction of the unused values which will be overwritten, `1.0` for all of them.
+///
+pub fn hide_secret_col_filled(pixels: &mut [Color], secret: &str, password: Option<SecretString>, fill: f64) -> Result<(), String> {
+    check_fraction(fill)?;
+
+    let written = encode_secret_col(pixels, secret, password)?;
+    fill_random(pixels, written, fill).map(|_| ())
+}
+
+/// Hides a secret string in a vector of pixels. Returns the number of values which were written.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secret will be hidden in.
+/// * `secret` - The secret string.
+///
+fn encode_secret_col(pixels: &mut [Color], secret: &str, password: Option<SecretString>) -> Result<usize, String> {
+    let secret_bytes = secret_to_bytes(secret, password)?;
+    let stego = Stego::default();
+
+    if stego.capacity(pixels) < secret_bytes.len() {
         return Err(String::from(
             "The message is too long to be hidden in the given pixel vector. Try using a shorter secret or a larger pixel vector.",
         ));
     }
 
-    let secret_bytes = match password {
-        Some(pwd) => otp(secret, &pwd).into_bytes(),
-        None => secret.to_owned().into_bytes(),
-    };
-
-    for (byte_idx, byte) in secret_bytes.iter().enumerate() {
-        // Index of the first (of three) Pixel
-        let n_rgb = 3 * byte_idx;
-
-        let bit_buffer = byte.to_bit_buffer();
-
-        for (bit_chunk_idx, bit_chunk) in bit_buffer.chunks(3).enumerate() {
-            // Calculate (image) row and column of the current pixel
-            let n: usize = n_rgb + bit_chunk_idx;
-
-            // Replace the least signbificant bits for R and G values of the Pixel.
-            let o_pixel = &pixels[n];
-            let r_out = o_pixel.r.set_lsb(bit_chunk[0]);
-            let g_out = o_pixel.g.set_lsb(bit_chunk[1]);
-
-            let b_out: u8 = if bit_chunk.len() > 2 {
-                // More than 2 bits in chunk, not the last chunk. We expect more chunks to come
-                o_pixel.b.set_lsb(bit_chunk[2])
-            } else if byte_idx + 1 == secret_bytes.len() {
-                // This is the last secret char, we set the last byte to be even.
-                o_pixel.b.set_lsb(false)
-            } else {
-                o_pixel.b.set_lsb(true) // Last chunk but not the last secret char, we set the last byte to be odd.
-            };
-
-            pixels[n] = Color::from_rgb(r_out, g_out, b_out);
-        }
-    }
-
-    Ok(())
+    stego.hide(pixels, &secret_bytes)
 }
 
 /// Returns a secret string retrieved from the provided pixel vector if it exists.
@@ -147,51 +153,8 @@ pub fn hide_secret_col(pixels: &mut Vec<Color>, secret: &String, password: Optio
 ///
 /// * `pixels` - Vector of pixels which will be searched for a secret string.
 ///
-pub fn find_secret_col(pixels: &Vec<Color>, password: Option<String>) -> Option<String> {
-    if pixels.len() < 3 {
-        return None;
-    }
-
-    let mut result: String = String::new();
-    let mut byte_array = vec![];
-
-    // Iterate chunks of three pixels
-    for pixel_chunk in pixels.chunks(3) {
-        let mut byte: u8 = 0;
-
-        // Construct a byte from three pixels
-        byte = byte.set_bit(0, pixel_chunk[0].r.get_lsb());
-        byte = byte.set_bit(1, pixel_chunk[0].g.get_lsb());
-        byte = byte.set_bit(2, pixel_chunk[0].b.get_lsb());
-        byte = byte.set_bit(3, pixel_chunk[1].r.get_lsb());
-        byte = byte.set_bit(4, pixel_chunk[1].g.get_lsb());
-        byte = byte.set_bit(5, pixel_chunk[1].b.get_lsb());
-        byte = byte.set_bit(6, pixel_chunk[2].r.get_lsb());
-        byte = byte.set_bit(7, pixel_chunk[2].g.get_lsb());
-
-        byte_array.push(byte);
-
-        // Last byte (blue) value of the last pixel in the chunk is even. This is the termination flag.
-        if !pixel_chunk[2].b.get_lsb() {
-            break;
-        }
-    }
-
-    // Try convert the byte array to (secret) string
-    match String::from_utf8(byte_array) {
-        Ok(res) => result = format!("{result}{res}"),
-        Err(_err) => return None,
-    }
-
-    if result.is_empty() || result == "\0" {
-        return None;
-    }
-
-    if let Some(pwd) = password {
-        result = otp(&result, &pwd)
-    }
-
-    Some(result)
+pub fn find_secret_col(pixels: &[Color], password: Option<SecretString>) -> Option<SecretString> {
+    bytes_to_secret(Stego::default().extract(pixels).ok()?, password)
 }
 
 #[cfg(feature = "image")]
@@ -201,7 +164,7 @@ pub fn find_secret_col(pixels: &Vec<Color>, password: Option<String>) -> Option<
 ///
 /// * `img_path` - Path to the image which will be searched for hidden secrets.
 ///
-pub fn find_secret_img(img_path: &str, password: Option<String>) -> Result<Option<String>, String> {
+pub fn find_secret_img(img_path: &str, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
     if let Ok(img) = image::open(img_path) {
         Ok(decode_secret_img(&img, password))
     } else {
@@ -217,54 +180,145 @@ pub fn find_secret_img(img_path: &str, password: Option<String>) -> Result<Optio
 ///
 /// * `img` - Image from which a secret will be retrieved.
 ///
-fn decode_secret_img(img: &DynamicImage, password: Option<String>) -> Option<String> {
-    if img.pixels().count() < 3 {
-        return None;
+pub fn decode_secret_img(img: &DynamicImage, password: Option<SecretString>) -> Option<SecretString> {
+    bytes_to_secret(Stego::default().extract(img).ok()?, password)
+}
+
+#[cfg(feature = "image")]
+/// Hides a secret in an encoded image, e.g. an uploaded file. The format of the input is detected from its content.
+/// Returns the image with the secret encoded in the given format, which has to be lossless.
+///
+/// # Arguments
+///
+/// * `bytes` - Encoded input image.
+/// * `secret` - Secret text which will be hidden in the image.
+/// * `format` - Lossless format of the returned image, e.g. `ImageFormat::Png`.
+///
+pub fn hide_secret_bytes(bytes: &[u8], secret: &str, password: Option<SecretString>, format: ImageFormat) -> Result<Vec<u8>, String> {
+    check_lossless(format)?;
+
+    let mut img = load_image_bytes(bytes)?;
+    encode_secret_img(&mut img, secret, password)?;
+    encode_image_bytes(&img, format)
+}
+
+#[cfg(feature = "image")]
+/// Searches an encoded image for hidden secrets. The format of the image is detected from its content.
+///
+/// # Arguments
+///
+/// * `bytes` - Encoded image which will be searched for hidden secrets.
+///
+pub fn find_secret_bytes(bytes: &[u8], password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    Ok(decode_secret_img(&load_image_bytes(bytes)?, password))
+}
+
+#[cfg(feature = "image")]
+/// Returns the image encoded in the given format. Returns Error for formats which may change pixel values.
+///
+/// # Arguments
+///
+/// * `img` - Image which will be encoded.
+/// * `format` - Lossless target format, e.g. `ImageFormat::Png`.
+///
+pub fn encode_image_bytes(img: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, String> {
+    check_lossless(format)?;
+
+    let mut bytes = Cursor::new(vec![]);
+    img.write_to(&mut bytes, format).map_err(|err| format!("Failed encoding the image as {format:?}: {err}"))?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(feature = "image")]
+/// Saves an image with a hidden secret to a file or writer. Only lossless formats are accepted, as any other format
+/// destroys the least significant bits. The encoded image is read back and compared with the original before
+/// anything is written, so an Error is returned instead of silently losing the secret or overwriting the target.
+///
+/// # Arguments
+///
+/// * `img` - Image with a hidden secret.
+/// * `output` - Path of the target file or a writer, e.g. `"peppers.png"` or `&mut vec`.
+/// * `format` - Lossless target format, e.g. `ImageFormat::Png`.
+///
+pub fn save_stego<'a>(img: &DynamicImage, output: impl Into<StegoOutput<'a>>, format: ImageFormat) -> Result<(), String> {
+    let bytes = encode_image_bytes(img, format)?;
+    verify_written(img, &bytes, format)?;
+
+    match output.into() {
+        StegoOutput::Path(path) => std::fs::write(path, &bytes).map_err(|err| format!("Failed writing output image '{}': {err}", path.display())),
+        StegoOutput::Writer(writer) => writer.write_all(&bytes).map_err(|err| format!("Failed writing output image: {err}")),
     }
+}
 
-    let mut result: String = String::new();
-    let mut byte_array = vec![];
+#[cfg(feature = "image")]
+/// Returns Error if the encoded image does not carry exactly the R, G and B values of the original image.
+fn verify_written(img: &DynamicImage, bytes: &[u8], format: ImageFormat) -> Result<(), String> {
+    let written = image::load_from_memory_with_format(bytes, format).map_err(|err| format!("Failed reading back output image: {err}"))?;
 
-    let pixels: Vec<_> = img.pixels().collect();
+    if written.width() != img.width() || written.height() != img.height() || written.to_rgb8() != img.to_rgb8() {
+        return Err(format!(
+            "The image written as {format:?} does not carry the hidden secret anymore. Try using a lossless format like PNG."
+        ));
+    }
 
-    // Iterate chunks of three pixels
-    for pixel_chunk in pixels.chunks(3) {
-        let mut byte: u8 = 0;
+    Ok(())
+}
 
-        // Construct a byte from three pixels
-        byte = byte.set_bit(0, pixel_chunk[0].2 .0[0].get_lsb());
-        byte = byte.set_bit(1, pixel_chunk[0].2 .0[1].get_lsb());
-        byte = byte.set_bit(2, pixel_chunk[0].2 .0[2].get_lsb());
-        byte = byte.set_bit(3, pixel_chunk[1].2 .0[0].get_lsb());
-        byte = byte.set_bit(4, pixel_chunk[1].2 .0[1].get_lsb());
-        byte = byte.set_bit(5, pixel_chunk[1].2 .0[2].get_lsb());
-        byte = byte.set_bit(6, pixel_chunk[2].2 .0[0].get_lsb());
-        byte = byte.set_bit(7, pixel_chunk[2].2 .0[1].get_lsb());
+#[cfg(feature = "image")]
+/// Returns Error if encoding an image in the format may change pixel values, which destroys hidden secrets.
+pub(crate) fn check_lossless(format: ImageFormat) -> Result<(), String> {
+    match format {
+        ImageFormat::Png | ImageFormat::Bmp | ImageFormat::Tiff | ImageFormat::Tga | ImageFormat::Pnm | ImageFormat::Qoi => Ok(()),
+        _ => Err(format!("The format {format:?} may change pixel values and destroy the secret. Try using a lossless format like PNG.")),
+    }
+}
 
-        byte_array.push(byte);
+#[cfg(feature = "image")]
+/// Returns the image decoded from bytes in any supported format.
+fn load_image_bytes(bytes: &[u8]) -> Result<DynamicImage, String> {
+    let format = image::guess_format(bytes).map_err(|_| String::from("The image format could not be detected. Try using a PNG, BMP or TIFF image."))?;
+    image::load_from_memory_with_format(bytes, format).map_err(|err| format!("Failed loading input image from memory: {err}"))
+}
 
-        // Last byte (blue) value of the last pixel in the chunk is even. This is the termination flag.
-        if !pixel_chunk[2].2 .0[2].get_lsb() {
-            break;
-        }
+/// Returns the bytes of a secret string which will be hidden in a carrier, encrypted if a password is provided.
+/// The bytes are wiped when they are dropped.
+///
+/// # Arguments
+///
+/// * `secret` - The secret string.
+/// * `password` - Optional password the secret will be encrypted with.
+///
+pub(crate) fn secret_to_bytes(secret: &str, password: Option<SecretString>) -> Result<SecretBytes, String> {
+    if secret.is_empty() {
+        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
     }
 
-    // Try convert the byte array to (secret) string
-    match String::from_utf8(byte_array) {
-        Ok(res) => result = format!("{result}{res}"),
-        Err(_err) => return None,
+    match password {
+        Some(pwd) => Ok(SecretBytes::from(otp(secret, &pwd).into_bytes())),
+        None => Ok(SecretBytes::from(secret.as_bytes())),
     }
+}
+
+/// Returns the secret string represented by bytes retrieved from a carrier if it is valid.
+/// The retrieved bytes are wiped unless they become the returned secret.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes retrieved from a carrier.
+/// * `password` - Optional password the secret will be decrypted with.
+///
+pub(crate) fn bytes_to_secret(bytes: Vec<u8>, password: Option<SecretString>) -> Option<SecretString> {
+    // Try convert the byte array to (secret) string
+    let result = SecretString::from_utf8(bytes)?;
 
-    if result.is_empty() || result == "\0" {
+    if result.is_empty() || result.expose() == "\0" {
         return None;
     }
 
     match password {
-        Some(pwd) => result = otp(&result, &pwd),
-        None => (),
+        Some(pwd) => Some(SecretString::from(otp(&result, &pwd))),
+        None => Some(result),
     }
-
-    Some(result)
 }
 
 #[cfg(test)]
@@ -283,7 +337,7 @@ pub mod tests {
         let secret = String::from("0123456789");
         let result = hide_secret_col(&mut pixels, &secret, None);
         assert!(result.is_ok());
-        assert_eq!(Some(secret.to_owned()), find_secret_col(&pixels, None));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_col(&pixels, None));
 
         // Return Error/None for byte vectors which cannot hold any secrets.
         let mut pixels = vec![Color::new(); 1];
@@ -296,27 +350,68 @@ pub mod tests {
 
         // Successfully encode decode the minimum size image secret combination.
         assert!(hide_secret_col(&mut pixels, &String::from("a"), None).is_ok());
-        assert_eq!(Some(String::from("a")), find_secret_col(&pixels, None));
+        assert_eq!(Some(SecretString::from("a")), find_secret_col(&pixels, None));
 
         // Providing an empty secret returns Error
         let mut pixels = vec![Color::new(); 30];
-        assert!(hide_secret_col(&mut pixels, &String::from(""), None).is_err());
+        assert!(hide_secret_col(&mut pixels, "", None).is_err());
 
         // Providing password will encrypt decrypt
         let mut pixels = vec![Color::new(); 30];
-        let password = String::from("Ipsum Lorem");
+        let password = SecretString::from("Ipsum Lorem");
         let result = hide_secret_col(&mut pixels, &secret, Some(password.to_owned()));
         assert!(result.is_ok());
 
         // Decoding with wrong password does not return secret
-        let wrong_secret = find_secret_col(&pixels, Some(String::from("Wrong password")));
+        let wrong_secret = find_secret_col(&pixels, Some(SecretString::from("Wrong password")));
         assert!(wrong_secret.is_some());
-        assert_ne!(wrong_secret.unwrap(), secret);
+        assert_ne!(wrong_secret.unwrap().expose(), secret);
 
         // Decoding with correct password returns correct secret
         let correct_secret = find_secret_col(&pixels, Some(password));
         assert!(correct_secret.is_some());
-        assert_eq!(correct_secret.unwrap(), secret);
+        assert_eq!(correct_secret.unwrap().expose(), secret);
+    }
+
+    #[test]
+    fn hide_secret_col_filled_ut() {
+        let secret = String::from("0123456789");
+        let password = SecretString::from("Ipsum Lorem");
+
+        // The secret is found and the values behind it are filled
+        let mut pixels = vec![Color::new(); 1000];
+        assert!(hide_secret_col_filled(&mut pixels, &secret, Some(password.to_owned()), 1.0).is_ok());
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_col(&pixels, Some(password)));
+        assert!(pixels[secret.len() * 3..].iter().any(|p| p.r == 1 || p.g == 1 || p.b == 1));
+
+        // Without fill the values behind the secret are untouched
+        let mut pixels = vec![Color::new(); 1000];
+        assert!(hide_secret_col_filled(&mut pixels, &secret, None, 0.0).is_ok());
+        assert!(pixels[secret.len() * 3..].iter().all(|p| *p == Color::new()));
+
+        // Invalid fill fractions return Error without touching the pixels
+        assert!(hide_secret_col_filled(&mut pixels, &String::from("a"), None, 2.0).is_err());
+        assert!(pixels[secret.len() * 3..].iter().all(|p| *p == Color::new()));
+        assert!(hide_secret_col_filled(&mut pixels, "", None, 1.0).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_secret_img_filled_ut() {
+        let secret = String::from("Lorem Ipsum");
+        let cover = image::open("test_images/peppers.png").unwrap();
+
+        let result = hide_secret_img_filled("test_images/peppers.png", &secret, None, 1.0);
+        assert!(result.is_ok());
+        let img = result.unwrap();
+        assert_eq!(Some(SecretString::from(secret.to_owned())), decode_secret_img(&img, None));
+
+        // Values far behind the secret are changed as well
+        let (cover, img) = (cover.to_rgb8(), img.to_rgb8());
+        assert!(cover.pixels().zip(img.pixels()).skip(1000).any(|(a, b)| a != b));
+
+        assert!(hide_secret_img_filled("test_images/non_existent_image", &secret, None, 1.0).is_err());
+        assert!(hide_secret_img_filled("test_images/peppers.png", &secret, None, -1.0).is_err());
     }
 
     #[test]
@@ -331,7 +426,7 @@ pub mod tests {
         let secret = String::from("0123456789");
         let result = encode_secret_img(&mut image, &secret, None);
         assert!(result.is_ok());
-        assert_eq!(Some(secret.to_owned()), decode_secret_img(&image, None));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), decode_secret_img(&image, None));
 
         // Return Error/None for images which cannot hold any secrets.
         let mut image = image::open("test_images/1x1.png").unwrap();
@@ -344,29 +439,90 @@ pub mod tests {
 
         // Successfully encode decode the minimum size image secret combination.
         assert!(encode_secret_img(&mut image, &String::from("a"), None).is_ok());
-        assert_eq!(Some(String::from("a")), decode_secret_img(&image, None));
+        assert_eq!(Some(SecretString::from("a")), decode_secret_img(&image, None));
 
         // Providing an empty secret returns Error
         let mut image = image::open("test_images/rgb.jpg").unwrap();
-        assert!(encode_secret_img(&mut image, &String::from(""), None).is_err());
+        assert!(encode_secret_img(&mut image, "", None).is_err());
 
         // Test with password
         let mut image = image::open("test_images/peppers.png").unwrap();
-        let password = String::from("Lorem Ipsum");
+        let password = SecretString::from("Lorem Ipsum");
         let result = encode_secret_img(&mut image, &secret, Some(password.to_owned()));
         assert!(result.is_ok());
 
         // No correct password provided fails to return correct secret
         let faulty = decode_secret_img(&image, None);
         assert!(faulty.is_some());
-        assert_ne!(faulty.unwrap(), secret);
-        let faulty = decode_secret_img(&image, Some(String::from("Wrong password")));
+        assert_ne!(faulty.unwrap().expose(), secret);
+        let faulty = decode_secret_img(&image, Some(SecretString::from("Wrong password")));
         assert!(faulty.is_some());
-        assert_ne!(faulty.unwrap(), secret);
+        assert_ne!(faulty.unwrap().expose(), secret);
 
         let correct = decode_secret_img(&image, Some(password));
         assert!(correct.is_some());
-        assert_eq!(correct.unwrap(), secret);
+        assert_eq!(correct.unwrap().expose(), secret);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_secret_bytes_ut() {
+        let secret = String::from("Lorem ipsum");
+        let png = std::fs::read("test_images/peppers.png").unwrap();
+
+        // Secrets survive every lossless output format
+        for format in [ImageFormat::Png, ImageFormat::Bmp, ImageFormat::Tiff, ImageFormat::Pnm, ImageFormat::Qoi] {
+            let bytes = hide_secret_bytes(&png, &secret, Some(SecretString::from("password")), format).unwrap();
+            assert_eq!(format, image::guess_format(&bytes).unwrap());
+            assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_bytes(&bytes, Some(SecretString::from("password"))));
+        }
+
+        // JPEG input is accepted, lossy output formats are rejected
+        let jpeg = std::fs::read("test_images/girl_with_a_pearl_earring.jpg").unwrap();
+        assert!(hide_secret_bytes(&jpeg, &secret, None, ImageFormat::Png).is_ok());
+        assert!(hide_secret_bytes(&png, &secret, None, ImageFormat::Jpeg).is_err());
+        assert!(encode_image_bytes(&image::open("test_images/peppers.png").unwrap(), ImageFormat::Gif).is_err());
+
+        // Bytes which are not an image return Error
+        assert!(hide_secret_bytes(b"Lorem ipsum", &secret, None, ImageFormat::Png).is_err());
+        assert!(find_secret_bytes(b"Lorem ipsum", None).is_err());
+        assert!(find_secret_bytes(&png[..100], None).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn save_stego_ut() {
+        let secret = String::from("Lorem ipsum");
+        let img = hide_secret_img("test_images/peppers.png", &secret, None).unwrap();
+
+        // Saving to a file verifies the written image
+        let path = std::env::temp_dir().join("hips_save_stego_ut.bmp");
+        assert!(save_stego(&img, path.as_path(), ImageFormat::Bmp).is_ok());
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_img(path.to_str().unwrap(), None));
+        std::fs::remove_file(&path).unwrap();
+
+        // Saving to a writer
+        let mut bytes = vec![];
+        assert!(save_stego(&img, &mut bytes, ImageFormat::Png).is_ok());
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_bytes(&bytes, None));
+
+        // Lossy formats are rejected without writing anything
+        let mut bytes = vec![];
+        assert!(save_stego(&img, &mut bytes, ImageFormat::Jpeg).is_err());
+        assert!(save_stego(&img, &mut bytes, ImageFormat::WebP).is_err());
+        assert!(bytes.is_empty());
+
+        // Images which differ from the original are detected
+        let cover = encode_image_bytes(&image::open("test_images/peppers.png").unwrap(), ImageFormat::Png).unwrap();
+        assert!(verify_written(&img, &cover, ImageFormat::Png).is_err());
+        assert!(save_stego(&img, "non_existent_dir/peppers.png", ImageFormat::Png).is_err());
+
+        // Images which cannot be read back leave an existing file untouched
+        let path = std::env::temp_dir().join("hips_save_stego_ut.pnm");
+        std::fs::write(&path, b"Lorem ipsum").unwrap();
+        assert!(save_stego(&DynamicImage::ImageRgba8(img.to_rgba8()), path.as_path(), ImageFormat::Pnm).is_err());
+        assert_eq!(b"Lorem ipsum".to_vec(), std::fs::read(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
@@ -386,31 +542,31 @@ pub mod tests {
         assert!(result.is_err());
 
         // Providing an empty secret returns Error
-        let result = hide_secret_img("test_images/rgb.jpg", &String::from(""), None);
+        let result = hide_secret_img("test_images/rgb.jpg", "", None);
         assert!(result.is_err());
 
         // Providing password will encrypt secret
-        let password = String::from("Ipsum Lorem");
+        let password = SecretString::from("Ipsum Lorem");
         let result = hide_secret_img("test_images/peppers.png", &secret, Some(password.to_owned()));
         assert!(result.is_ok());
 
         // No correct password provided fails returning secret
         let faulty = decode_secret_img(&result.unwrap(), None);
         assert!(faulty.is_some());
-        assert_ne!(faulty.unwrap(), secret);
+        assert_ne!(faulty.unwrap().expose(), secret);
 
         let result = hide_secret_img("test_images/peppers.png", &secret, Some(password));
-        let faulty = decode_secret_img(&result.unwrap(), Some(String::from("Wrong password")));
+        let faulty = decode_secret_img(&result.unwrap(), Some(SecretString::from("Wrong password")));
         assert!(faulty.is_some());
-        assert_ne!(faulty.unwrap(), secret);
+        assert_ne!(faulty.unwrap().expose(), secret);
 
         // Providing password will encrypt secret
-        let password = String::from("Ipsum Lorem");
+        let password = SecretString::from("Ipsum Lorem");
         let result = hide_secret_img("test_images/peppers.png", &secret, Some(password.to_owned()));
         assert!(result.is_ok());
         let correct = decode_secret_img(&result.unwrap(), Some(password));
         assert!(correct.is_some());
-        assert_eq!(correct.unwrap(), secret);
+        assert_eq!(correct.unwrap().expose(), secret);
     }
 
     #[test]
@@ -432,24 +588,24 @@ pub mod tests {
 
         // Test with password
         let expected = String::from("Lorem ipsum");
-        let password = String::from("password");
+        let password = SecretString::from("password");
 
         // No password provided return wrong secret
         let result = find_secret_img("test_images/image_with_secret_password.png", None);
         assert!(result.is_ok());
         assert!(result.to_owned().unwrap().is_some());
-        assert_ne!(result.unwrap().unwrap(), expected);
+        assert_ne!(result.unwrap().unwrap().expose(), expected);
 
         // Wrong password provided returns wrong secret
-        let result = find_secret_img("test_images/image_with_secret_password.png", Some(String::from("Wrong password")));
+        let result = find_secret_img("test_images/image_with_secret_password.png", Some(SecretString::from("Wrong password")));
         assert!(result.is_ok());
         assert!(result.to_owned().unwrap().is_some());
-        assert_ne!(result.unwrap().unwrap(), expected);
+        assert_ne!(result.unwrap().unwrap().expose(), expected);
 
         // Correct password provided returns correct secret
         let result = find_secret_img("test_images/image_with_secret_password.png", Some(password));
         assert!(result.is_ok());
         assert!(result.to_owned().unwrap().is_some());
-        assert_eq!(result.unwrap().unwrap(), expected);
+        assert_eq!(result.unwrap().unwrap().expose(), expected);
     }
 }
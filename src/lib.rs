@@ -1,4 +1,31 @@
+#[cfg(feature = "animation")]
+pub mod animation;
+pub mod audio;
 pub mod bit_ops;
+#[cfg(feature = "crypto")]
+pub mod bound;
+pub mod carrier;
 pub mod color;
+#[cfg(feature = "crypto")]
+mod crypto;
+#[cfg(feature = "crypto")]
+pub mod deniable;
 pub mod hips;
 pub mod otp;
+#[cfg(feature = "palette")]
+pub mod palette;
+pub mod quality;
+#[cfg(feature = "crypto")]
+pub mod sealed;
+pub mod secret;
+pub mod shamir;
+#[cfg(feature = "crypto")]
+pub mod signed;
+pub mod slots;
+pub mod steganalysis;
+pub mod stego;
+pub mod stripe;
+pub mod text;
+pub mod video;
+#[cfg(feature = "image")]
+pub mod visualize;
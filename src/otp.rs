@@ -11,7 +11,8 @@ pub fn otp(secret: &str, password: &str) -> String {
         return secret.to_string();
     }
 
-    let mut result: String = String::new();
+    // Every byte becomes a char of at most two bytes, reserving them up front avoids leaving copies behind on reallocation
+    let mut result: String = String::with_capacity(secret.len() * 2);
     for (idx, sec_byte) in secret.bytes().enumerate() {
         let pw_idx = idx % password.len();
         let c = (sec_byte ^ password.as_bytes().get(pw_idx).unwrap()) as char;
@@ -0,0 +1,520 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use crate::carrier::{capacity, embed, extract};
+use crate::color::Color;
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::SecretString;
+
+/// An image whose pixels are indices into a color palette, as stored in indexed PNG and GIF files.
+#[derive(Clone)]
+pub struct IndexedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Palette entries, transparency is stored in the alpha values.
+    pub palette: Vec<Color>,
+    /// Palette index of every pixel, row by row.
+    pub indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Tries to load an indexed PNG or GIF image. The format is detected from the file contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `img_path` - Path to the image file.
+    ///
+    pub fn open(img_path: &str) -> Result<IndexedImage, String> {
+        let mut file = File::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+
+        let mut signature = [0u8; 4];
+        file.read_exact(&mut signature).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+        let file = File::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+
+        match &signature {
+            b"\x89PNG" => IndexedImage::read_png(BufReader::new(file)),
+            b"GIF8" => IndexedImage::read_gif(BufReader::new(file)),
+            _ => Err(format!("The image '{img_path}' is neither a PNG nor a GIF image.")),
+        }
+    }
+
+    /// Tries to save the image, the format (PNG or GIF) is chosen by the file extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `img_path` - Path of the target image file.
+    ///
+    pub fn save(&self, img_path: &str) -> Result<(), String> {
+        let extension = img_path.rsplit('.').next().unwrap_or_default().to_lowercase();
+        if extension != "png" && extension != "gif" {
+            return Err(format!("Indexed images can only be saved as PNG or GIF, not as '{extension}'."));
+        }
+
+        let file = BufWriter::new(File::create(img_path).map_err(|_| format!("Failed creating output image '{img_path}'"))?);
+        match extension.as_str() {
+            "png" => self.write_png(file),
+            _ => self.write_gif(file),
+        }
+    }
+
+    /// Tries to decode an indexed PNG image.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the encoded PNG image.
+    ///
+    pub fn read_png<R: Read>(reader: R) -> Result<IndexedImage, String> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let mut reader = decoder.read_info().map_err(|err| format!("Failed decoding PNG image: {err}"))?;
+
+        let info = reader.info();
+        if info.color_type != png::ColorType::Indexed {
+            return Err(String::from("The PNG image is not an indexed (palette) image."));
+        }
+
+        let (width, height) = (info.width, info.height);
+        let bit_depth = info.bit_depth as usize;
+        let trns = info.trns.as_ref().map(|t| t.to_vec()).unwrap_or_default();
+        let palette: Vec<Color> = match &info.palette {
+            Some(plte) => plte
+                .chunks(3)
+                .enumerate()
+                .map(|(idx, rgb)| Color::from_rgba(rgb[0], rgb[1], rgb[2], *trns.get(idx).unwrap_or(&255)))
+                .collect(),
+            None => return Err(String::from("The PNG image does not contain a palette.")),
+        };
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let output = reader.next_frame(&mut buffer).map_err(|err| format!("Failed decoding PNG image: {err}"))?;
+
+        Ok(IndexedImage {
+            width,
+            height,
+            palette,
+            indices: unpack_indices(&buffer, output.line_size, width as usize, height as usize, bit_depth),
+        })
+    }
+
+    /// Tries to encode the image as indexed PNG image using the smallest possible bit depth.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Target of the encoded PNG image.
+    ///
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), String> {
+        let bit_depth = png_bit_depth(self.palette.len());
+
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(bit_depth);
+        encoder.set_palette(self.palette.iter().flat_map(|c| [c.r, c.g, c.b]).collect::<Vec<u8>>());
+        if self.palette.iter().any(|c| c.a != 255) {
+            encoder.set_trns(self.palette.iter().map(|c| c.a).collect::<Vec<u8>>());
+        }
+
+        let data = pack_indices(&self.indices, self.width as usize, bit_depth as usize);
+        let mut writer = encoder.write_header().map_err(|err| format!("Failed encoding PNG image: {err}"))?;
+        writer.write_image_data(&data).map_err(|err| format!("Failed encoding PNG image: {err}"))
+    }
+
+    /// Tries to decode the first frame of a GIF image.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the encoded GIF image.
+    ///
+    pub fn read_gif<R: Read>(reader: R) -> Result<IndexedImage, String> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(reader).map_err(|err| format!("Failed decoding GIF image: {err}"))?;
+
+        let global_palette = decoder.global_palette().map(|p| p.to_vec());
+        match decoder.read_next_frame().map_err(|err| format!("Failed decoding GIF image: {err}"))? {
+            Some(frame) => Ok(IndexedImage::from_gif_frame(frame, global_palette.as_deref())),
+            None => Err(String::from("The GIF image does not contain any frames.")),
+        }
+    }
+
+    /// Tries to encode the image as GIF image.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Target of the encoded GIF image.
+    ///
+    pub fn write_gif<W: Write>(&self, writer: W) -> Result<(), String> {
+        if self.palette.len() > 256 || self.width > u16::MAX as u32 || self.height > u16::MAX as u32 {
+            return Err(String::from("The image exceeds the limits of the GIF format."));
+        }
+
+        let mut encoder = gif::Encoder::new(writer, self.width as u16, self.height as u16, &self.gif_palette()).map_err(|err| format!("Failed encoding GIF image: {err}"))?;
+        encoder.write_frame(&self.to_gif_frame()).map_err(|err| format!("Failed encoding GIF image: {err}"))
+    }
+
+    /// Returns the image stored in a decoded GIF frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Decoded GIF frame in indexed color output.
+    /// * `global_palette` - Global palette of the GIF image, used if the frame has no local palette.
+    ///
+    pub(crate) fn from_gif_frame(frame: &gif::Frame, global_palette: Option<&[u8]>) -> IndexedImage {
+        let palette = frame.palette.as_deref().or(global_palette).unwrap_or_default();
+
+        IndexedImage {
+            width: frame.width as u32,
+            height: frame.height as u32,
+            palette: palette
+                .chunks(3)
+                .enumerate()
+                .map(|(idx, rgb)| Color::from_rgba(rgb[0], rgb[1], rgb[2], if frame.transparent == Some(idx as u8) { 0 } else { 255 }))
+                .collect(),
+            indices: frame.buffer.to_vec(),
+        }
+    }
+
+    /// Returns the image as GIF frame with timing and disposal set to their defaults.
+    pub(crate) fn to_gif_frame(&self) -> gif::Frame<'static> {
+        let transparent = self.palette.iter().position(|c| c.a == 0).map(|idx| idx as u8);
+        gif::Frame::from_indexed_pixels(self.width as u16, self.height as u16, self.indices.clone(), transparent)
+    }
+
+    /// Returns the palette as flat RGB triples as expected by the GIF encoder.
+    pub(crate) fn gif_palette(&self) -> Vec<u8> {
+        self.palette.iter().flat_map(|c| [c.r, c.g, c.b]).collect()
+    }
+}
+
+/// Tries to load an indexed PNG or GIF image and hide the given secret in it.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the image.
+///
+pub fn hide_secret_palette(img_path: &str, secret: &str, password: Option<SecretString>) -> Result<IndexedImage, String> {
+    let mut img = IndexedImage::open(img_path)?;
+    hide_secret_indexed(&mut img, secret, password)?;
+    Ok(img)
+}
+
+/// Tries to load an indexed PNG or GIF image and searches it for hidden secrets.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for hidden secrets.
+///
+pub fn find_secret_palette(img_path: &str, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let img = IndexedImage::open(img_path)?;
+    Ok(find_secret_indexed(&img, password))
+}
+
+/// Hides a secret string in the palette indices of an indexed image.
+///
+/// The opaque palette entries are sorted by luminance, so neighbouring entries in the sorted palette look alike (EzStego).
+/// Every pixel carries one bit in the least significant bit of its position in the sorted palette,
+/// i.e. a pixel is at most swapped with its most similar neighbour. Pixels with a transparent or translucent color
+/// never carry bits, so transparency is preserved. Palette colors are never changed, the palette is only padded to
+/// a power of two with copies of its last entry so the sorted order survives saving the image as GIF.
+///
+/// # Arguments
+///
+/// * `img` - Target indexed image the secret will be written to.
+/// * `secret` - Secret string which will be hidden in the target image.
+///
+pub fn hide_secret_indexed(img: &mut IndexedImage, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    let secret_bytes = secret_to_bytes(secret, password)?;
+
+    if indexed_capacity(img) < secret_bytes.len() {
+        return Err(String::from(
+            "The message is too long to be hidden in this picture. Try using a shorter message or a larger input image.",
+        ));
+    }
+
+    embed_indexed(img, &secret_bytes)
+}
+
+/// Returns a secret string retrieved from the palette indices of an indexed image if it exists.
+///
+/// # Arguments
+///
+/// * `img` - Indexed image from which a secret will be retrieved.
+///
+pub fn find_secret_indexed(img: &IndexedImage, password: Option<SecretString>) -> Option<SecretString> {
+    bytes_to_secret(extract_indexed(img), password)
+}
+
+/// Returns the number of bytes which can be hidden in the palette indices of an indexed image once its palette is padded.
+///
+/// # Arguments
+///
+/// * `img` - Indexed image which will carry the bytes.
+///
+pub fn indexed_capacity(img: &IndexedImage) -> usize {
+    let mut palette = img.palette.clone();
+    pad_palette(&mut palette);
+    capacity(sorted_ranks(&palette, &img.indices).1.len())
+}
+
+/// Hides a byte sequence in the palette indices of an indexed image.
+///
+/// # Arguments
+///
+/// * `img` - Target indexed image the bytes will be written to.
+/// * `bytes` - Bytes which will be hidden in the target image.
+///
+pub(crate) fn embed_indexed(img: &mut IndexedImage, bytes: &[u8]) -> Result<(), String> {
+    pad_palette(&mut img.palette);
+    let (positions, mut ranks) = sorted_ranks(&img.palette, &img.indices);
+
+    embed(ranks.as_mut_slice(), bytes)?;
+
+    let order = luminance_order(&img.palette);
+    for (position, rank) in positions.into_iter().zip(ranks) {
+        img.indices[position] = order[rank as usize];
+    }
+
+    Ok(())
+}
+
+/// Returns the byte sequence hidden in the palette indices of an indexed image.
+///
+/// # Arguments
+///
+/// * `img` - Indexed image which will be searched for hidden bytes.
+///
+pub(crate) fn extract_indexed(img: &IndexedImage) -> Vec<u8> {
+    extract(sorted_ranks(&img.palette, &img.indices).1.as_slice())
+}
+
+/// Pads the palette to a power of two with copies of its last entry, so the padding adds no new colors.
+pub(crate) fn pad_palette(palette: &mut Vec<Color>) {
+    if let Some(last) = palette.last().cloned() {
+        if palette.len() <= 256 {
+            palette.resize(palette.len().next_power_of_two().max(2), last);
+        }
+    }
+}
+
+/// Returns the luminance of a color.
+fn luminance(color: &Color) -> u32 {
+    299 * color.r as u32 + 587 * color.g as u32 + 114 * color.b as u32
+}
+
+/// Returns the indices of the opaque palette entries sorted by luminance. Entries of equal luminance keep their palette order.
+///
+/// Transparent and translucent entries are left out, so they are never swapped with visible colors.
+/// The brightest entry is left out as well if it has no partner.
+fn luminance_order(palette: &[Color]) -> Vec<u8> {
+    let mut order: Vec<u8> = (0..palette.len().min(256)).filter(|idx| palette[*idx].a == 255).map(|idx| idx as u8).collect();
+    order.sort_by_key(|idx| luminance(&palette[*idx as usize]));
+    order.truncate(order.len() / 2 * 2);
+    order
+}
+
+/// Returns the positions of all pixels which can carry a bit together with their rank in the sorted palette.
+///
+/// Ranks are paired (0 and 1, 2 and 3, ...) within the palette padded to a power of two.
+/// Pixels with an index outside of the sorted palette are skipped.
+fn sorted_ranks(palette: &[Color], indices: &[u8]) -> (Vec<usize>, Vec<u8>) {
+    let order = luminance_order(palette);
+    let mut rank = [None; 256];
+    for (r, idx) in order.iter().enumerate() {
+        rank[*idx as usize] = Some(r as u8);
+    }
+
+    indices.iter().enumerate().filter_map(|(position, idx)| Some((position, rank[*idx as usize]?))).unzip()
+}
+
+/// Returns the smallest PNG bit depth which can address all entries of a palette with the given size.
+pub(crate) fn png_bit_depth(palette_len: usize) -> png::BitDepth {
+    match palette_len {
+        0..=2 => png::BitDepth::One,
+        3..=4 => png::BitDepth::Two,
+        5..=16 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    }
+}
+
+/// Returns one palette index per pixel from rows of packed indices.
+pub(crate) fn unpack_indices(data: &[u8], line_size: usize, width: usize, height: usize, bit_depth: usize) -> Vec<u8> {
+    let per_byte = 8 / bit_depth;
+    let mask = ((1u16 << bit_depth) - 1) as u8;
+
+    let mut result = Vec::with_capacity(width * height);
+    for row in data.chunks(line_size).take(height) {
+        for x in 0..width {
+            let shift = 8 - bit_depth * (x % per_byte + 1);
+            result.push((row[x / per_byte] >> shift) & mask);
+        }
+    }
+
+    result
+}
+
+/// Returns rows of packed indices from one palette index per pixel.
+pub(crate) fn pack_indices(indices: &[u8], width: usize, bit_depth: usize) -> Vec<u8> {
+    let per_byte = 8 / bit_depth;
+    let line_size = width.div_ceil(per_byte);
+
+    let mut result = vec![];
+    for row in indices.chunks(width) {
+        let mut line = vec![0u8; line_size];
+        for (x, idx) in row.iter().enumerate() {
+            let shift = 8 - bit_depth * (x % per_byte + 1);
+            line[x / per_byte] |= idx << shift;
+        }
+        result.extend(line);
+    }
+
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Returns a gradient image using a shuffled grey palette of the given size.
+    fn test_image(palette_size: usize) -> IndexedImage {
+        let palette: Vec<Color> = (0..palette_size).map(|n| ((n * 37) % palette_size) as u8).map(|v| Color::from_rgb(v, v, v)).collect();
+        IndexedImage {
+            width: 40,
+            height: 30,
+            palette,
+            indices: (0..1200).map(|n| (n % palette_size) as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn encode_decode_secret_indexed_ut() {
+        let mut img = test_image(16);
+        let original = img.clone();
+
+        // Image with no secret returning None
+        let secret = String::from("0123456789");
+        assert_ne!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&img, None));
+
+        // Successfully encode and decode a valid secret
+        assert!(hide_secret_indexed(&mut img, &secret, None).is_ok());
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&img, None));
+
+        // The palette is untouched and pixels only move to a neighbour of similar luminance
+        let order = luminance_order(&img.palette);
+        for (before, after) in original.indices.iter().zip(&img.indices) {
+            let rank_before = order.iter().position(|idx| idx == before).unwrap();
+            let rank_after = order.iter().position(|idx| idx == after).unwrap();
+            assert!(rank_before.abs_diff(rank_after) <= 1);
+        }
+
+        // Providing password will encrypt decrypt
+        let password = SecretString::from("Lorem Ipsum");
+        assert!(hide_secret_indexed(&mut img, &secret, Some(password.to_owned())).is_ok());
+        assert_ne!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&img, None));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&img, Some(password)));
+
+        // Return Error when secret is too long for given image
+        let mut img = test_image(16);
+        assert!(hide_secret_indexed(&mut img, &"a".repeat(200), None).is_err());
+
+        // Palettes are padded to a power of two with copies of the last entry
+        let mut img = test_image(5);
+        assert!(hide_secret_indexed(&mut img, &secret, None).is_ok());
+        assert_eq!(8, img.palette.len());
+        assert!(img.palette[5..].iter().all(|c| *c == img.palette[4]));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&img, None));
+
+        // The capacity counts the pixels which only carry bits once the palette is padded
+        let mut img = test_image(3);
+        let filling = "a".repeat(indexed_capacity(&img));
+        assert!(capacity(800) < indexed_capacity(&img));
+        assert!(hide_secret_indexed(&mut img, &filling, None).is_ok());
+        assert_eq!(Some(SecretString::from(filling)), find_secret_indexed(&img, None));
+
+        // Transparent colors and the brightest opaque color without partner are never changed
+        let mut img = test_image(4);
+        img.palette[1].a = 0;
+        let original = img.clone();
+        assert!(hide_secret_indexed(&mut img, &secret, None).is_ok());
+        for (before, after) in original.indices.iter().zip(&img.indices) {
+            let color = &original.palette[*before as usize];
+            if color.a == 0 || color.r == 3 {
+                assert_eq!(before, after);
+            }
+        }
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&img, None));
+
+        // A palette with a single visible color cannot carry bits
+        let mut img = test_image(2);
+        img.palette[1].a = 0;
+        assert_eq!(0, indexed_capacity(&img));
+        assert!(hide_secret_indexed(&mut img, &secret, None).is_err());
+    }
+
+    #[test]
+    fn png_gif_round_trip_ut() {
+        let secret = String::from("Lorem ipsum");
+
+        for palette_size in [3, 4, 16, 200] {
+            let mut img = test_image(palette_size);
+            img.palette[1].a = 0;
+            let original = img.clone();
+            assert!(hide_secret_indexed(&mut img, &secret, None).is_ok());
+
+            // Transparent pixels stay transparent and visible pixels stay visible
+            for (before, after) in original.indices.iter().zip(&img.indices) {
+                assert_eq!(original.palette[*before as usize].a, img.palette[*after as usize].a);
+            }
+
+            // Indexed PNG keeps indices, palette and transparency
+            let mut buffer = vec![];
+            assert!(img.write_png(&mut buffer).is_ok());
+            let png = IndexedImage::read_png(buffer.as_slice()).unwrap();
+            assert_eq!(img.indices, png.indices);
+            assert_eq!(0, png.palette[1].a);
+            assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&png, None));
+
+            // GIF keeps indices, palette and transparency
+            let mut buffer = vec![];
+            assert!(img.write_gif(&mut buffer).is_ok());
+            let gif = IndexedImage::read_gif(buffer.as_slice()).unwrap();
+            assert_eq!(img.indices, gif.indices);
+            assert_eq!(0, gif.palette[1].a);
+            assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_indexed(&gif, None));
+        }
+
+        // True color PNG images are rejected
+        let file = File::open("test_images/1x1.png").unwrap();
+        assert!(IndexedImage::read_png(BufReader::new(file)).is_err());
+    }
+
+    #[test]
+    fn hide_find_secret_palette_ut() {
+        // Non existent images return Error
+        assert!(hide_secret_palette("test_images/non_existent_image.gif", "Lorem", None).is_err());
+        assert!(find_secret_palette("test_images/non_existent_image.gif", None).is_err());
+
+        // Images which are neither PNG nor GIF return Error
+        assert!(find_secret_palette("test_images/rgb.jpg", None).is_err());
+
+        // Successfully hide a secret in an indexed PNG image
+        let secret = String::from("Lorem ipsum");
+        let password = SecretString::from("password");
+        let result = hide_secret_palette("test_images/peppers.png", &secret, Some(password.to_owned()));
+        assert!(result.is_ok());
+
+        // The secret survives saving the image as PNG and GIF
+        let img = result.unwrap();
+        for extension in ["png", "gif"] {
+            let path = std::env::temp_dir().join(format!("hips_palette_ut.{extension}"));
+            let path = path.to_str().unwrap();
+            assert!(img.save(path).is_ok());
+            assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_palette(path, Some(password.to_owned())));
+            std::fs::remove_file(path).unwrap();
+        }
+
+        // Unsupported extensions are rejected without creating the file
+        let path = std::env::temp_dir().join("hips_palette_ut.bmp");
+        assert!(img.save(path.to_str().unwrap()).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::color::Color;
+
+#[cfg(feature = "image")]
+use image::{DynamicImage, GenericImageView};
+
+/// Width and height of the windows SSIM is computed on.
+const SSIM_WINDOW: usize = 8;
+
+/// Offset between neighbouring SSIM windows.
+const SSIM_STEP: usize = 4;
+
+/// Distortion introduced by hiding a secret in an image. Only the R, G and B values are compared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityReport {
+    /// Mean squared error over all R, G and B values.
+    pub mse: f64,
+    /// Peak signal-to-noise ratio in decibel, infinite for identical images.
+    pub psnr: f64,
+    /// Mean structural similarity of the R, G and B channels, one for identical images.
+    pub ssim: f64,
+    /// Number of pixels with at least one changed R, G or B value.
+    pub changed_pixels: usize,
+    /// Number of changed R, G and B values.
+    pub changed_channels: usize,
+}
+
+/// Pixels laid out in rows which can be compared by [`quality_report`].
+///
+pub trait Pixels {
+    /// Returns the width and height in pixels.
+    ///
+    fn size(&self) -> (usize, usize);
+
+    /// Returns the R, G and B values of the pixel on the specified position, counted row by row.
+    ///
+    fn rgb(&self, idx: usize) -> [u8; 3];
+}
+
+/// Vectors of pixels are treated as a single row.
+impl Pixels for [Color] {
+    fn size(&self) -> (usize, usize) {
+        (self.len(), 1)
+    }
+
+    fn rgb(&self, idx: usize) -> [u8; 3] {
+        [self[idx].r, self[idx].g, self[idx].b]
+    }
+}
+
+#[cfg(feature = "image")]
+impl Pixels for DynamicImage {
+    fn size(&self) -> (usize, usize) {
+        (self.width() as usize, self.height() as usize)
+    }
+
+    fn rgb(&self, idx: usize) -> [u8; 3] {
+        let idx = idx as u32;
+        let pixel = self.get_pixel(idx % self.width(), idx / self.width());
+        [pixel[0], pixel[1], pixel[2]]
+    }
+}
+
+/// Measures the distortion between a cover and its stego image.
+///
+/// # Arguments
+///
+/// * `cover` - Original pixels.
+/// * `stego` - Pixels with a hidden secret.
+///
+pub fn quality_report<P: Pixels + ?Sized>(cover: &P, stego: &P) -> Result<QualityReport, String> {
+    if cover.size() != stego.size() {
+        return Err(String::from("The images have different dimensions. Try using the cover image of the stego image."));
+    }
+
+    let (width, height) = cover.size();
+    if width * height == 0 {
+        return Err(String::from("The images are empty. Try using images with at least one pixel."));
+    }
+
+    let mut channels: [(Vec<u8>, Vec<u8>); 3] = Default::default();
+    let (mut squared_error, mut changed_pixels, mut changed_channels) = (0.0, 0, 0);
+    for idx in 0..width * height {
+        let (original, changed) = (cover.rgb(idx), stego.rgb(idx));
+        let mut pixel_changed = false;
+
+        for (channel, (a, b)) in original.into_iter().zip(changed).enumerate() {
+            squared_error += (a as f64 - b as f64).powi(2);
+            if a != b {
+                changed_channels += 1;
+                pixel_changed = true;
+            }
+            channels[channel].0.push(a);
+            channels[channel].1.push(b);
+        }
+
+        if pixel_changed {
+            changed_pixels += 1;
+        }
+    }
+
+    let mse = squared_error / (width * height * 3) as f64;
+    let ssim = channels.iter().map(|(a, b)| ssim(a, b, width, height)).sum::<f64>() / 3.0;
+
+    Ok(QualityReport {
+        mse,
+        psnr: 10.0 * (255.0 * 255.0 / mse).log10(),
+        ssim,
+        changed_pixels,
+        changed_channels,
+    })
+}
+
+/// Returns the mean structural similarity of two channels over overlapping windows.
+/// Windows are reduced to the image size for images smaller than a window.
+fn ssim(cover: &[u8], stego: &[u8], width: usize, height: usize) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (window_width, window_height) = (SSIM_WINDOW.min(width), SSIM_WINDOW.min(height));
+    let mut sum = 0.0;
+    let mut windows = 0;
+
+    for top in (0..=height - window_height).step_by(SSIM_STEP) {
+        for left in (0..=width - window_width).step_by(SSIM_STEP) {
+            let values = (top..top + window_height)
+                .flat_map(|y| (left..left + window_width).map(move |x| y * width + x))
+                .map(|idx| (cover[idx] as f64, stego[idx] as f64));
+
+            let n = (window_width * window_height) as f64;
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for (a, b) in values {
+                sum_a += a;
+                sum_b += b;
+                sum_aa += a * a;
+                sum_bb += b * b;
+                sum_ab += a * b;
+            }
+
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let (variance_a, variance_b) = (sum_aa / n - mean_a * mean_a, sum_bb / n - mean_b * mean_b);
+            let covariance = sum_ab / n - mean_a * mean_b;
+
+            sum += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2)) / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            windows += 1;
+        }
+    }
+
+    sum / windows as f64
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn quality_report_col_ut() {
+        let cover: Vec<Color> = (0..100).map(|n| Color::from_rgb(n as u8, 2 * n as u8, 255 - n as u8)).collect();
+
+        // Identical pixels are not distorted
+        let report = quality_report(cover.as_slice(), cover.as_slice()).unwrap();
+        assert_eq!(0.0, report.mse);
+        assert_eq!(f64::INFINITY, report.psnr);
+        assert!((report.ssim - 1.0).abs() < 1e-9);
+        assert_eq!((0, 0), (report.changed_pixels, report.changed_channels));
+
+        // Single values changed by one
+        let mut stego = cover.clone();
+        stego[0].r += 1;
+        stego[0].g += 1;
+        stego[50].b -= 1;
+        let report = quality_report(cover.as_slice(), stego.as_slice()).unwrap();
+        assert!((report.mse - 0.01).abs() < 1e-9);
+        assert!((report.psnr - 10.0 * (255.0f64 * 255.0 / 0.01).log10()).abs() < 1e-9);
+        assert!(report.ssim < 1.0 && report.ssim > 0.99);
+        assert_eq!((2, 3), (report.changed_pixels, report.changed_channels));
+
+        // Different or empty inputs return Error
+        assert!(quality_report(cover.as_slice(), &stego[1..]).is_err());
+        assert!(quality_report(&cover[..0], &stego[..0]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn quality_report_img_ut() {
+        use crate::hips::hide_secret_img;
+
+        let cover = image::open("test_images/peppers.png").unwrap();
+        let stego = hide_secret_img("test_images/peppers.png", "Lorem ipsum dolor sit amet.", None).unwrap();
+
+        // Only the pixels carrying the secret may change and only in the least significant bit
+        let report = quality_report(&cover, &stego).unwrap();
+        assert!(report.changed_pixels > 0 && report.changed_pixels <= 28 * 3);
+        assert!(report.changed_channels >= report.changed_pixels && report.changed_channels <= 28 * 9);
+        assert_eq!(report.changed_channels as f64 / (200.0 * 200.0 * 3.0), report.mse);
+        assert!(report.psnr > 60.0);
+        assert!(report.ssim > 0.99);
+
+        assert!(quality_report(&cover, &DynamicImage::new_rgb8(1, 1)).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::carrier::{capacity, embed, extract, Carrier};
+use crate::color::Color;
+use crate::crypto::{open, random_key, seal, NONCE_LEN, TAG_LEN};
+use crate::secret::SecretString;
+
+pub use x25519_dalek::{PublicKey, StaticSecret};
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// Number of bytes of the ephemeral public key in front of every sealed payload.
+pub const EPHEMERAL_KEY_LEN: usize = 32;
+
+/// Returns a new random X25519 key pair. The public key is handed out to senders, the secret key stays with the recipient.
+pub fn generate_keypair() -> Result<(StaticSecret, PublicKey), String> {
+    let secret = StaticSecret::from(*random_key()?);
+    let public = PublicKey::from(&secret);
+    Ok((secret, public))
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and hide the given secret in it, encrypted to the public key of the recipient.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the image.
+/// * `recipient` - Public key of the recipient.
+///
+pub fn hide_secret_img_sealed(img_path: &str, secret: &str, recipient: &PublicKey) -> Result<DynamicImage, String> {
+    let mut img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    hide_sealed(&mut img, secret, recipient)?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and searches it for a secret encrypted to the given secret key.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for hidden secrets.
+/// * `secret_key` - Secret key of the recipient.
+///
+pub fn find_secret_img_sealed(img_path: &str, secret_key: &StaticSecret) -> Result<Option<SecretString>, String> {
+    let img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    Ok(find_sealed(&img, secret_key))
+}
+
+/// Hides a secret in a vector of pixels, encrypted to the public key of the recipient.
+///
+/// Every secret is encrypted with a key agreed between a fresh ephemeral key pair and the recipient's key,
+/// so any number of senders can hide secrets which only the recipient is able to read.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secret will be hidden in.
+/// * `secret` - The secret string.
+/// * `recipient` - Public key of the recipient.
+///
+pub fn hide_secret_col_sealed(pixels: &mut [Color], secret: &str, recipient: &PublicKey) -> Result<(), String> {
+    hide_sealed(pixels, secret, recipient)
+}
+
+/// Returns a secret encrypted to the given secret key retrieved from the provided pixel vector if it exists.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels which will be searched for a secret string.
+/// * `secret_key` - Secret key of the recipient.
+///
+pub fn find_secret_col_sealed(pixels: &[Color], secret_key: &StaticSecret) -> Option<SecretString> {
+    find_sealed(pixels, secret_key)
+}
+
+/// Encrypts bytes to the public key of the recipient. Returns the ephemeral public key followed by the sealed bytes.
+pub(crate) fn seal_to(recipient: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let (ephemeral_secret, ephemeral_public) = generate_keypair()?;
+    let shared = ephemeral_secret.diffie_hellman(recipient);
+
+    let mut sealed = ephemeral_public.as_bytes().to_vec();
+    sealed.extend(seal(&box_key(shared.as_bytes(), &ephemeral_public, recipient), plaintext)?);
+    Ok(sealed)
+}
+
+/// Returns the bytes encrypted by [`seal_to`], or `None` if they were not encrypted to the given secret key.
+pub(crate) fn open_from(secret_key: &StaticSecret, sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < EPHEMERAL_KEY_LEN {
+        return None;
+    }
+
+    let (ephemeral_public, sealed) = sealed.split_at(EPHEMERAL_KEY_LEN);
+    let ephemeral_public = PublicKey::from(<[u8; 32]>::try_from(ephemeral_public).ok()?);
+    let shared = secret_key.diffie_hellman(&ephemeral_public);
+    open(&box_key(shared.as_bytes(), &ephemeral_public, &PublicKey::from(secret_key)), sealed)
+}
+
+/// Returns the symmetric key derived from the shared secret and both public keys.
+fn box_key(shared: &[u8; 32], ephemeral_public: &PublicKey, recipient: &PublicKey) -> Zeroizing<[u8; 32]> {
+    let key = Sha256::new()
+        .chain_update("hips-lib sealed box")
+        .chain_update(shared)
+        .chain_update(ephemeral_public.as_bytes())
+        .chain_update(recipient.as_bytes())
+        .finalize();
+    Zeroizing::new(key.into())
+}
+
+fn hide_sealed<C: Carrier + ?Sized>(carrier: &mut C, secret: &str, recipient: &PublicKey) -> Result<(), String> {
+    if secret.is_empty() {
+        return Err(String::from("You have entered an empty secret. Try to use at least one character in the secret text."));
+    }
+
+    if capacity(carrier.sample_count()) < EPHEMERAL_KEY_LEN + NONCE_LEN + secret.len() + TAG_LEN {
+        return Err(String::from("The message is too long to be hidden in this carrier. Try using a shorter message or a larger carrier."));
+    }
+
+    embed(carrier, &seal_to(recipient, secret.as_bytes())?).map(|_| ())
+}
+
+fn find_sealed<C: Carrier + ?Sized>(carrier: &C, secret_key: &StaticSecret) -> Option<SecretString> {
+    SecretString::from_utf8(open_from(secret_key, &extract(carrier))?)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_to_open_from_ut() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let (other_key, _) = generate_keypair().unwrap();
+
+        let sealed = seal_to(&public_key, b"Lorem ipsum").unwrap();
+        assert_eq!(EPHEMERAL_KEY_LEN + NONCE_LEN + 11 + TAG_LEN, sealed.len());
+        assert_eq!(Some(b"Lorem ipsum".to_vec()), open_from(&secret_key, &sealed));
+
+        // Every message uses a fresh ephemeral key
+        assert_ne!(sealed[..EPHEMERAL_KEY_LEN], seal_to(&public_key, b"Lorem ipsum").unwrap()[..EPHEMERAL_KEY_LEN]);
+
+        // Other secret keys and truncated payloads are rejected
+        assert_eq!(None, open_from(&other_key, &sealed));
+        assert_eq!(None, open_from(&secret_key, &sealed[..EPHEMERAL_KEY_LEN - 1]));
+    }
+
+    #[test]
+    fn hide_find_secret_col_sealed_ut() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let (other_key, _) = generate_keypair().unwrap();
+        let secret = String::from("Lorem ipsum");
+        let mut pixels = vec![Color::new(); 300];
+
+        // Pixels with no secret return None
+        assert_eq!(None, find_secret_col_sealed(&pixels, &secret_key));
+
+        // Only the recipient finds the secret
+        assert!(hide_secret_col_sealed(&mut pixels, &secret, &public_key).is_ok());
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_secret_col_sealed(&pixels, &secret_key));
+        assert_eq!(None, find_secret_col_sealed(&pixels, &other_key));
+
+        // Empty secrets or too small pixel vectors return Error
+        assert!(hide_secret_col_sealed(&mut pixels, "", &public_key).is_err());
+        assert!(hide_secret_col_sealed(&mut pixels[..10], &secret, &public_key).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_secret_img_sealed_ut() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let path = std::env::temp_dir().join("hips_sealed_ut.png");
+        let path = path.to_str().unwrap();
+
+        hide_secret_img_sealed("test_images/peppers.png", "Lorem ipsum", &public_key).unwrap().save(path).unwrap();
+        assert_eq!(Ok(Some(SecretString::from("Lorem ipsum"))), find_secret_img_sealed(path, &secret_key));
+        assert_eq!(Ok(None), find_secret_img_sealed("test_images/peppers.png", &secret_key));
+        std::fs::remove_file(path).unwrap();
+
+        assert!(hide_secret_img_sealed("test_images/non_existent_image", "Lorem ipsum", &public_key).is_err());
+        assert!(find_secret_img_sealed("test_images/non_existent_image", &secret_key).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use std::ops::Deref;
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A string holding a password or a secret text which is overwritten with zeros when it is dropped.
+///
+/// Dereferences to `str`, so it can be passed wherever a `&str` is expected.
+/// The `Debug` output never reveals the content.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+/// A byte vector holding sensitive data which is overwritten with zeros when it is dropped.
+///
+/// Dereferences to `[u8]`, so it can be passed wherever a `&[u8]` is expected.
+/// The `Debug` output never reveals the content.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretString {
+    /// Returns the wrapped string.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the bytes as string if they are valid UTF-8. Invalid bytes are wiped.
+    pub(crate) fn from_utf8(bytes: Vec<u8>) -> Option<Self> {
+        match String::from_utf8(bytes) {
+            Ok(string) => Some(SecretString(string)),
+            Err(err) => {
+                err.into_bytes().zeroize();
+                None
+            }
+        }
+    }
+}
+
+impl SecretBytes {
+    /// Returns the wrapped bytes.
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        SecretString(value.to_owned())
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(value: Vec<u8>) -> Self {
+        SecretBytes(value)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(value: &[u8]) -> Self {
+        SecretBytes(value.to_vec())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretBytes(***)")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretString {}
+
+impl ZeroizeOnDrop for SecretBytes {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_string_ut() {
+        let secret = SecretString::from("password");
+        assert_eq!("password", secret.expose());
+        assert_eq!(8, secret.len());
+        assert_eq!("SecretString(***)", format!("{secret:?}"));
+
+        let secret = SecretString::from(String::from("password"));
+        assert_eq!("password", &*secret.clone());
+    }
+
+    #[test]
+    fn secret_bytes_ut() {
+        let bytes = SecretBytes::from(vec![1, 2, 3]);
+        assert_eq!(&[1, 2, 3], bytes.expose());
+        assert_eq!(3, bytes.len());
+        assert_eq!("SecretBytes(***)", format!("{bytes:?}"));
+        assert_eq!(&[1, 2, 3][..], &*SecretBytes::from(&[1u8, 2, 3][..]));
+    }
+}
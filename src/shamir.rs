@@ -0,0 +1,292 @@
+use crate::carrier::{capacity, embed, extract, Carrier};
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::{SecretBytes, SecretString};
+use zeroize::Zeroizing;
+
+/// Marks a payload as share, followed by the format version.
+const MAGIC: &[u8; 4] = b"HSS\x01";
+
+/// Number of bytes in front of every share: magic bytes, the random id of the set of shares,
+/// the threshold and the index of the share.
+pub const SHARE_HEADER_LEN: usize = MAGIC.len() + 4 + 2;
+
+/// Splits bytes into shares with Shamir's secret sharing over GF(256), any `threshold` of the shares
+/// reconstruct the bytes while fewer shares reveal nothing about them.
+/// Returns the shares together with their index (`1..=shares`).
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes which will be split.
+/// * `threshold` - Number of shares needed to reconstruct the bytes.
+/// * `shares` - Number of shares which will be created.
+///
+pub fn split(bytes: &[u8], threshold: u8, shares: u8) -> Result<Vec<(u8, Vec<u8>)>, String> {
+    if threshold == 0 || threshold > shares {
+        return Err(format!(
+            "The threshold {threshold} is invalid for {shares} shares. Try using a threshold between 1 and the number of shares."
+        ));
+    }
+
+    // Every byte is the constant term of its own random polynomial of degree threshold - 1
+    let mut coefficients = Zeroizing::new(vec![0u8; bytes.len() * (threshold as usize - 1)]);
+    getrandom::getrandom(&mut coefficients).map_err(|err| format!("Failed generating random bytes: {err}"))?;
+
+    let result = (1..=shares)
+        .map(|x| {
+            let share = bytes
+                .iter()
+                .enumerate()
+                .map(|(idx, byte)| {
+                    let polynomial = &coefficients[idx * (threshold as usize - 1)..(idx + 1) * (threshold as usize - 1)];
+                    // Horner's method, highest coefficient first
+                    let higher = polynomial.iter().rev().fold(0, |sum, c| gf_mul(sum, x) ^ c);
+                    gf_mul(higher, x) ^ byte
+                })
+                .collect();
+            (x, share)
+        })
+        .collect();
+
+    Ok(result)
+}
+
+/// Reconstructs bytes from shares created by [`split`]. At least as many shares as the threshold have to be
+/// provided, otherwise the result is meaningless.
+///
+/// # Arguments
+///
+/// * `shares` - Shares together with their index.
+///
+pub fn combine<S: AsRef<[u8]>>(shares: &[(u8, S)]) -> Result<Vec<u8>, String> {
+    if shares.is_empty() {
+        return Err(String::from("There are no shares to combine."));
+    }
+    if shares.iter().any(|(x, share)| *x == 0 || share.as_ref().len() != shares[0].1.as_ref().len()) {
+        return Err(String::from("The shares are invalid. Try using shares of the same secret."));
+    }
+    if (1..shares.len()).any(|idx| shares[..idx].iter().any(|(x, _)| *x == shares[idx].0)) {
+        return Err(String::from("The shares contain duplicate indices."));
+    }
+
+    // Lagrange interpolation at x = 0
+    let weights: Vec<u8> = shares
+        .iter()
+        .map(|(x, _)| shares.iter().filter(|(other, _)| other != x).fold(1, |weight, (other, _)| gf_mul(weight, gf_div(*other, other ^ x))))
+        .collect();
+
+    let len = shares[0].1.as_ref().len();
+    Ok((0..len)
+        .map(|idx| shares.iter().zip(&weights).fold(0, |sum, ((_, share), weight)| sum ^ gf_mul(share.as_ref()[idx], *weight)))
+        .collect())
+}
+
+/// Splits a secret with Shamir's secret sharing and hides one share in every cover, so any `threshold`
+/// of the covers reconstruct the secret. Every share is preceded by a header with the id of the set,
+/// the threshold and the index of the share.
+///
+/// # Arguments
+///
+/// * `covers` - Carriers the shares will be hidden in, e.g. images or pixel vectors. One share is created per cover.
+/// * `secret` - Secret text which will be split.
+/// * `threshold` - Number of covers needed to reconstruct the secret.
+///
+pub fn hide_secret_shares<C: Carrier + ?Sized>(covers: &mut [&mut C], secret: &str, password: Option<SecretString>, threshold: u8) -> Result<(), String> {
+    if covers.len() > u8::MAX as usize {
+        return Err(String::from("The secret can be split into at most 255 shares. Try using fewer covers."));
+    }
+
+    let secret_bytes = secret_to_bytes(secret, password)?;
+    let shares: Vec<(u8, SecretBytes)> = split(&secret_bytes, threshold, covers.len() as u8)?
+        .into_iter()
+        .map(|(x, share)| (x, SecretBytes::from(share)))
+        .collect();
+
+    if covers.iter().any(|cover| capacity(cover.sample_count()) < SHARE_HEADER_LEN + secret_bytes.len()) {
+        return Err(String::from("The message is too long to be hidden in these covers. Try using a shorter message or larger covers."));
+    }
+
+    let mut id = [0u8; 4];
+    getrandom::getrandom(&mut id).map_err(|err| format!("Failed generating random bytes: {err}"))?;
+
+    for (cover, (x, share)) in covers.iter_mut().zip(shares) {
+        let mut payload = Vec::with_capacity(SHARE_HEADER_LEN + share.len());
+        payload.extend(MAGIC);
+        payload.extend(id);
+        payload.extend([threshold, x]);
+        payload.extend(share.expose());
+        embed(*cover, &SecretBytes::from(payload))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the secret reconstructed from the shares hidden in the carriers. The carriers may be provided
+/// in any order, carriers without a share are ignored. If the carriers hold shares of several secrets,
+/// the first secret with enough shares is reconstructed. Returns Error if no secret reaches its threshold.
+///
+/// # Arguments
+///
+/// * `carriers` - Carriers which will be searched for shares.
+///
+pub fn find_secret_shares<C: Carrier + ?Sized>(carriers: &[&C], password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let payloads: Vec<SecretBytes> = carriers
+        .iter()
+        .map(|carrier| SecretBytes::from(extract(*carrier)))
+        .filter(|payload| payload.len() > SHARE_HEADER_LEN && payload.starts_with(MAGIC))
+        .collect();
+
+    if payloads.is_empty() {
+        return Err(String::from("The carriers do not contain any shares."));
+    }
+
+    // Group the shares by the id of their secret, in the order the secrets are first seen
+    let mut groups: Vec<(&[u8], u8, Vec<_>)> = vec![];
+    for payload in &payloads {
+        let (id, threshold, x) = (&payload[MAGIC.len()..MAGIC.len() + 4], payload[MAGIC.len() + 4], payload[SHARE_HEADER_LEN - 1]);
+        let idx = match groups.iter().position(|(other, _, _)| *other == id) {
+            Some(idx) => idx,
+            None => {
+                groups.push((id, threshold, vec![]));
+                groups.len() - 1
+            }
+        };
+
+        let shares = &mut groups[idx].2;
+        if shares.iter().all(|(other, _)| *other != x) {
+            shares.push((x, &payload[SHARE_HEADER_LEN..]));
+        }
+    }
+
+    let (_, threshold, mut shares) = match groups.iter().position(|(_, threshold, shares)| shares.len() >= *threshold as usize) {
+        Some(idx) => groups.swap_remove(idx),
+        None => {
+            let (_, threshold, shares) = groups.iter().max_by_key(|(_, _, shares)| shares.len()).unwrap();
+            return Err(format!("Only {} of {threshold} shares needed to reconstruct the secret were found.", shares.len()));
+        }
+    };
+
+    shares.truncate(threshold as usize);
+    Ok(bytes_to_secret(combine(&shares)?, password))
+}
+
+/// Multiplies two elements of GF(256) with the reduction polynomial x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Divides two elements of GF(256), the divisor must not be zero.
+fn gf_div(a: u8, b: u8) -> u8 {
+    // The inverse of b is b^254
+    let inverse = (0..7).fold((b, 1), |(power, inverse), _| {
+        let power = gf_mul(power, power);
+        (power, gf_mul(inverse, power))
+    });
+    gf_mul(a, inverse.1)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn gf_ut() {
+        assert_eq!(0xc1, gf_mul(0x57, 0x83));
+        assert_eq!(0, gf_mul(0x57, 0));
+        for a in 1..=255 {
+            assert_eq!(1, gf_mul(a, gf_div(1, a)));
+            assert_eq!(a, gf_div(gf_mul(a, 0x53), 0x53));
+        }
+    }
+
+    #[test]
+    fn split_combine_ut() {
+        let bytes = b"Lorem ipsum dolor sit amet".to_vec();
+        let shares = split(&bytes, 3, 5).unwrap();
+        assert_eq!(5, shares.len());
+        assert!(shares.iter().all(|(_, share)| share.len() == bytes.len() && *share != bytes));
+
+        // Any three shares reconstruct the bytes
+        for skip in 0..5 {
+            let subset: Vec<(u8, Vec<u8>)> = shares.iter().cycle().skip(skip).take(3).cloned().collect();
+            assert_eq!(Ok(bytes.to_owned()), combine(&subset));
+        }
+
+        // Two shares do not reconstruct the bytes
+        assert_ne!(Ok(bytes.to_owned()), combine(&shares[..2]));
+
+        // Invalid thresholds and shares return Error
+        assert!(split(&bytes, 0, 5).is_err());
+        assert!(split(&bytes, 6, 5).is_err());
+        assert!(combine::<Vec<u8>>(&[]).is_err());
+        assert!(combine(&[shares[0].to_owned(), shares[0].to_owned()]).is_err());
+        assert!(combine(&[(0, vec![1])]).is_err());
+    }
+
+    #[test]
+    fn hide_find_secret_shares_ut() {
+        let secret = String::from("Lorem ipsum");
+        let password = SecretString::from("password");
+        let mut covers = vec![vec![Color::new(); 100]; 4];
+
+        {
+            let mut refs: Vec<&mut [Color]> = covers.iter_mut().map(|cover| cover.as_mut_slice()).collect();
+            assert!(hide_secret_shares(&mut refs, &secret, Some(password.to_owned()), 3).is_ok());
+        }
+
+        // Any three covers in any order reconstruct the secret
+        let found = find_secret_shares(&[covers[3].as_slice(), covers[0].as_slice(), covers[2].as_slice()], Some(password.to_owned()));
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), found);
+        let found = find_secret_shares(&[covers[1].as_slice(), covers[2].as_slice(), covers[1].as_slice(), covers[3].as_slice()], Some(password.to_owned()));
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), found);
+
+        // Two covers or covers without shares return Error
+        let empty = vec![Color::new(); 100];
+        assert!(find_secret_shares(&[covers[0].as_slice(), covers[1].as_slice(), empty.as_slice()], Some(password.to_owned())).is_err());
+        assert!(find_secret_shares(&[empty.as_slice()], None).is_err());
+
+        // Shares of another secret are skipped, even if they come first
+        let mut foreign = vec![vec![Color::new(); 100]; 2];
+        {
+            let mut refs: Vec<&mut [Color]> = foreign.iter_mut().map(|cover| cover.as_mut_slice()).collect();
+            assert!(hide_secret_shares(&mut refs, "dolor sit amet", None, 2).is_ok());
+        }
+        let found = find_secret_shares(&[foreign[0].as_slice(), covers[0].as_slice(), covers[1].as_slice(), covers[2].as_slice()], Some(password.to_owned()));
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), found);
+        let found = find_secret_shares(&[foreign[0].as_slice(), covers[0].as_slice(), covers[1].as_slice(), foreign[1].as_slice()], None);
+        assert_eq!(Ok(Some(SecretString::from("dolor sit amet"))), found);
+        assert!(find_secret_shares(&[foreign[0].as_slice(), covers[0].as_slice(), covers[1].as_slice()], None).is_err());
+
+        // Covers which are too small or invalid thresholds return Error
+        let mut small = vec![vec![Color::new(); 10]; 3];
+        let mut refs: Vec<&mut [Color]> = small.iter_mut().map(|cover| cover.as_mut_slice()).collect();
+        assert!(hide_secret_shares(&mut refs, &secret, None, 2).is_err());
+        let mut refs: Vec<&mut [Color]> = covers.iter_mut().map(|cover| cover.as_mut_slice()).collect();
+        assert!(hide_secret_shares(&mut refs, &secret, None, 5).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_secret_shares_img_ut() {
+        let secret = String::from("Lorem ipsum");
+        let mut a = image::open("test_images/peppers.png").unwrap();
+        let mut b = a.clone();
+        let mut c = a.clone();
+
+        assert!(hide_secret_shares(&mut [&mut a, &mut b, &mut c], &secret, None, 2).is_ok());
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_shares(&[&c, &a], None));
+        assert!(find_secret_shares(&[&b], None).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use ed25519_dalek::{Signature, Signer, Verifier};
+use zeroize::Zeroizing;
+
+use crate::carrier::{capacity, embed, extract, Carrier};
+use crate::color::Color;
+use crate::crypto::{cover_hash, random_key};
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::SecretString;
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// Marks a payload as signed, followed by the format version.
+const MAGIC: &[u8; 4] = b"HSG\x01";
+
+/// Flag marking signatures which cover the high bits of the carrier as well.
+const FLAG_COVER: u8 = 0b0000_0001;
+
+/// Separates signatures of hidden payloads from other signatures made with the same key.
+const SIGNATURE_CONTEXT: &[u8] = b"hips-lib signature";
+
+/// Number of bytes in front of every signed secret: magic bytes, flags and the signature.
+pub const SIGNED_HEADER_LEN: usize = MAGIC.len() + 1 + Signature::BYTE_SIZE;
+
+/// Result of checking the signature of a hidden secret against a trusted public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// The secret was signed by the trusted key and neither the secret nor the bound cover were modified.
+    Valid,
+    /// The secret was signed by another key, or the secret or the bound cover were modified.
+    Invalid,
+}
+
+/// A secret retrieved from a carrier together with the result of verifying its signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedSecret {
+    pub secret: SecretString,
+    pub verification: Verification,
+    /// Whether the signature covers the high bits of the carrier as well.
+    pub cover_bound: bool,
+}
+
+/// Returns a new random Ed25519 signing key. Its verifying key is handed out to recipients.
+pub fn generate_signing_key() -> Result<SigningKey, String> {
+    Ok(SigningKey::from_bytes(&*random_key()?))
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and hide the given secret in it, signed with the signing key.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `secret` - Secret text which will be hidden in the image.
+/// * `signing_key` - Key the secret will be signed with.
+/// * `bind_cover` - Whether the signature covers the high bits of the image as well.
+///
+pub fn hide_secret_img_signed(img_path: &str, secret: &str, password: Option<SecretString>, signing_key: &SigningKey, bind_cover: bool) -> Result<DynamicImage, String> {
+    let mut img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    hide_signed(&mut img, secret, password, signing_key, bind_cover)?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and searches it for a signed secret, verified with the trusted key.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for hidden secrets.
+/// * `trusted` - Verifying key of the expected signer.
+///
+pub fn find_secret_img_signed(img_path: &str, password: Option<SecretString>, trusted: &VerifyingKey) -> Result<Option<SignedSecret>, String> {
+    let img = image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))?;
+    Ok(find_signed(&img, password, trusted))
+}
+
+/// Hides a secret in a vector of pixels together with an Ed25519 signature, so recipients holding the
+/// verifying key can tell who hid the secret.
+///
+/// If `bind_cover` is set, the signature covers a hash of all bits which are not used for hiding as well,
+/// so copying the least significant bits onto another picture invalidates the signature.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the secret will be hidden in.
+/// * `secret` - The secret string.
+/// * `signing_key` - Key the secret will be signed with.
+/// * `bind_cover` - Whether the signature covers the high bits of the pixels as well.
+///
+pub fn hide_secret_col_signed(pixels: &mut [Color], secret: &str, password: Option<SecretString>, signing_key: &SigningKey, bind_cover: bool) -> Result<(), String> {
+    hide_signed(pixels, secret, password, signing_key, bind_cover)
+}
+
+/// Returns a signed secret retrieved from the provided pixel vector if it exists, verified with the trusted key.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels which will be searched for a secret string.
+/// * `trusted` - Verifying key of the expected signer.
+///
+pub fn find_secret_col_signed(pixels: &[Color], password: Option<SecretString>, trusted: &VerifyingKey) -> Option<SignedSecret> {
+    find_signed(pixels, password, trusted)
+}
+
+/// Returns the message which is signed: a context string, the flags, the hash of the cover if it is bound
+/// and the hidden bytes.
+fn signed_message<C: Carrier + ?Sized>(carrier: &C, flags: u8, bytes: &[u8]) -> Vec<u8> {
+    let mut message = SIGNATURE_CONTEXT.to_vec();
+    message.push(flags);
+    if flags & FLAG_COVER != 0 {
+        message.extend(cover_hash(carrier));
+    }
+    message.extend(bytes);
+    message
+}
+
+fn hide_signed<C: Carrier + ?Sized>(carrier: &mut C, secret: &str, password: Option<SecretString>, signing_key: &SigningKey, bind_cover: bool) -> Result<(), String> {
+    let secret_bytes = secret_to_bytes(secret, password)?;
+
+    if capacity(carrier.sample_count()) < SIGNED_HEADER_LEN + secret_bytes.len() {
+        return Err(String::from("The message is too long to be hidden in this carrier. Try using a shorter message or a larger carrier."));
+    }
+
+    let flags = if bind_cover { FLAG_COVER } else { 0 };
+    let signature = signing_key.sign(&signed_message(carrier, flags, &secret_bytes));
+
+    let mut payload = Zeroizing::new(MAGIC.to_vec());
+    payload.push(flags);
+    payload.extend(signature.to_bytes());
+    payload.extend_from_slice(&secret_bytes);
+
+    embed(carrier, &payload).map(|_| ())
+}
+
+fn find_signed<C: Carrier + ?Sized>(carrier: &C, password: Option<SecretString>, trusted: &VerifyingKey) -> Option<SignedSecret> {
+    let payload = extract(carrier);
+    if payload.len() <= SIGNED_HEADER_LEN || !payload.starts_with(MAGIC) {
+        return None;
+    }
+
+    let flags = payload[MAGIC.len()];
+    let signature = Signature::from_bytes(payload[MAGIC.len() + 1..SIGNED_HEADER_LEN].try_into().unwrap());
+    let bytes = &payload[SIGNED_HEADER_LEN..];
+
+    let verification = match trusted.verify(&signed_message(carrier, flags, bytes), &signature) {
+        Ok(_) => Verification::Valid,
+        Err(_) => Verification::Invalid,
+    };
+
+    Some(SignedSecret {
+        secret: bytes_to_secret(bytes.to_vec(), password)?,
+        verification,
+        cover_bound: flags & FLAG_COVER != 0,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn hide_find_secret_col_signed_ut() {
+        let signing_key = generate_signing_key().unwrap();
+        let trusted = signing_key.verifying_key();
+        let other = generate_signing_key().unwrap().verifying_key();
+        let secret = String::from("Lorem ipsum");
+        let password = SecretString::from("password");
+
+        // Pixels with no secret return None
+        let mut pixels = vec![Color::new(); 300];
+        assert_eq!(None, find_secret_col_signed(&pixels, None, &trusted));
+
+        // Only the trusted key verifies the signature
+        assert!(hide_secret_col_signed(&mut pixels, &secret, Some(password.to_owned()), &signing_key, false).is_ok());
+        let found = find_secret_col_signed(&pixels, Some(password.to_owned()), &trusted).unwrap();
+        assert_eq!(
+            (SecretString::from(secret.to_owned()), Verification::Valid, false),
+            (found.secret, found.verification, found.cover_bound)
+        );
+        assert_eq!(Verification::Invalid, find_secret_col_signed(&pixels, Some(password.to_owned()), &other).unwrap().verification);
+
+        // Modifying the high bits keeps unbound signatures valid
+        pixels[299].r = 100;
+        assert_eq!(Verification::Valid, find_secret_col_signed(&pixels, Some(password.to_owned()), &trusted).unwrap().verification);
+
+        // Modifying a hidden bit invalidates the signature
+        let idx = SIGNED_HEADER_LEN * 9;
+        pixels[idx / 3].r ^= 1;
+        assert_eq!(Verification::Invalid, find_secret_col_signed(&pixels, None, &trusted).unwrap().verification);
+
+        // Empty secrets or too small pixel vectors return Error
+        assert!(hide_secret_col_signed(&mut pixels, "", None, &signing_key, false).is_err());
+        assert!(hide_secret_col_signed(&mut pixels[..50], &secret, None, &signing_key, false).is_err());
+    }
+
+    #[test]
+    fn bind_cover_ut() {
+        let signing_key = generate_signing_key().unwrap();
+        let trusted = signing_key.verifying_key();
+        let secret = String::from("Lorem ipsum");
+
+        let mut pixels: Vec<Color> = (0..300)
+            .map(|idx| Color {
+                r: idx as u8,
+                g: (idx * 7) as u8,
+                b: (idx * 13) as u8,
+                a: 255,
+            })
+            .collect();
+        assert!(hide_secret_col_signed(&mut pixels, &secret, None, &signing_key, true).is_ok());
+        let found = find_secret_col_signed(&pixels, None, &trusted).unwrap();
+        assert_eq!((Verification::Valid, true), (found.verification, found.cover_bound));
+
+        // Copying the least significant bits onto another cover invalidates the signature
+        let mut other: Vec<Color> = pixels.iter().map(|p| Color { r: p.r ^ 2, ..*p }).collect();
+        let found = find_secret_col_signed(&other, None, &trusted).unwrap();
+        assert_eq!((SecretString::from(secret.to_owned()), Verification::Invalid), (found.secret, found.verification));
+
+        // Modifying high bits of the cover behind the secret invalidates the signature as well
+        other = pixels.clone();
+        other[299].g ^= 0b1000_0000;
+        assert_eq!(Verification::Invalid, find_secret_col_signed(&other, None, &trusted).unwrap().verification);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_secret_img_signed_ut() {
+        let signing_key = generate_signing_key().unwrap();
+        let trusted = signing_key.verifying_key();
+        let path = std::env::temp_dir().join("hips_signed_ut.png");
+        let path = path.to_str().unwrap();
+
+        hide_secret_img_signed("test_images/peppers.png", "Lorem ipsum", None, &signing_key, true).unwrap().save(path).unwrap();
+        let found = find_secret_img_signed(path, None, &trusted).unwrap().unwrap();
+        assert_eq!((SecretString::from("Lorem ipsum"), Verification::Valid, true), (found.secret, found.verification, found.cover_bound));
+        assert_eq!(Ok(None), find_secret_img_signed("test_images/peppers.png", None, &trusted));
+        std::fs::remove_file(path).unwrap();
+
+        assert!(hide_secret_img_signed("test_images/non_existent_image", "Lorem ipsum", None, &signing_key, false).is_err());
+        assert!(find_secret_img_signed("test_images/non_existent_image", None, &trusted).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::carrier::{embed, extract, fill_random_range, Carrier, SAMPLES_PER_BYTE};
+use crate::color::Color;
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::{SecretBytes, SecretString};
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// Marks a payload as slot table, followed by the format version.
+const MAGIC: &[u8; 5] = b"HIPS\x01";
+
+/// Named payloads hidden together in a single carrier.
+///
+/// The table is stored as magic bytes, the number of slots as little endian `u16` and every slot as
+/// name length (`u8`), name, data length (little endian `u32`) and data. Every slot can be encrypted with its own password.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotTable {
+    slots: Vec<(String, SecretBytes)>,
+}
+
+impl SlotTable {
+    pub fn new() -> Self {
+        SlotTable::default()
+    }
+
+    /// Returns the slot table hidden in the carrier. Carriers without a slot table return an empty table.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier which will be searched for a slot table.
+    ///
+    pub fn read<C: Carrier + ?Sized>(carrier: &C) -> Self {
+        SlotTable::from_bytes(&SecretBytes::from(extract(carrier))).unwrap_or_default()
+    }
+
+    /// Hides the slot table in the carrier, replacing any previously hidden payload.
+    /// Samples of a previous, longer slot table are overwritten with random bits, so removed or shortened slots leave nothing behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier the slot table will be hidden in.
+    ///
+    pub fn write<C: Carrier + ?Sized>(&self, carrier: &mut C) -> Result<(), String> {
+        let previous = SecretBytes::from(extract(carrier));
+        let previous_end = if previous.starts_with(MAGIC) { previous.len() * SAMPLES_PER_BYTE } else { 0 };
+
+        let end = embed(carrier, &self.to_bytes()).map_err(|_| String::from("The slots are too long to be hidden in this carrier. Try using shorter secrets, fewer slots or a larger carrier."))?;
+        fill_random_range(carrier, end..previous_end, 1.0).map(|_| ())
+    }
+
+    /// Returns the names of all slots in insertion order.
+    pub fn names(&self) -> Vec<&str> {
+        self.slots.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Adds a slot or replaces the slot with the same name. Other slots are left untouched.
+    /// A table holds at most 65 535 slots.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the slot, at most 255 bytes.
+    /// * `secret` - Secret text which will be stored in the slot.
+    ///
+    pub fn insert(&mut self, name: &str, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+        if name.is_empty() || name.len() > u8::MAX as usize {
+            return Err(String::from("The slot name is invalid. Try using a name with 1 to 255 bytes."));
+        }
+
+        let data = secret_to_bytes(secret, password)?;
+        if data.len() > u32::MAX as usize {
+            return Err(String::from("The secret is too long to be stored in a slot. Try using a shorter secret."));
+        }
+
+        let full = self.slots.len() >= u16::MAX as usize;
+        match self.slots.iter_mut().find(|(slot, _)| slot == name) {
+            Some(slot) => slot.1 = data,
+            None if full => return Err(String::from("The slot table is full. Try removing a slot or replacing an existing one.")),
+            None => self.slots.push((String::from(name), data)),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the slot with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the slot which will be removed.
+    ///
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        let len = self.slots.len();
+        self.slots.retain(|(slot, _)| slot != name);
+
+        if self.slots.len() == len {
+            return Err(format!("The slot '{name}' does not exist."));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the secret stored in the slot with the given name if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the slot.
+    ///
+    pub fn find(&self, name: &str, password: Option<SecretString>) -> Option<SecretString> {
+        let (_, data) = self.slots.iter().find(|(slot, _)| slot == name)?;
+        bytes_to_secret(data.to_vec(), password)
+    }
+
+    fn to_bytes(&self) -> SecretBytes {
+        // Reserving the whole table up front avoids leaving copies of the secrets behind on reallocation
+        let len = MAGIC.len() + 2 + self.slots.iter().map(|(name, data)| 1 + name.len() + 4 + data.len()).sum::<usize>();
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend(MAGIC);
+        bytes.extend((self.slots.len() as u16).to_le_bytes());
+
+        for (name, data) in &self.slots {
+            bytes.push(name.len() as u8);
+            bytes.extend(name.as_bytes());
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend(data.expose());
+        }
+
+        SecretBytes::from(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut rest = bytes.strip_prefix(MAGIC)?;
+        let mut take = |len: usize| -> Option<&[u8]> {
+            let (head, tail) = (rest.get(..len)?, rest.get(len..)?);
+            rest = tail;
+            Some(head)
+        };
+
+        let count = u16::from_le_bytes(take(2)?.try_into().ok()?);
+        let mut slots = vec![];
+        for _ in 0..count {
+            let name_len = take(1)?[0] as usize;
+            let name = String::from_utf8(take(name_len)?.to_vec()).ok()?;
+            let data_len = u32::from_le_bytes(take(4)?.try_into().ok()?) as usize;
+            slots.push((name, SecretBytes::from(take(data_len)?)));
+        }
+
+        Some(SlotTable { slots })
+    }
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and hide a secret in the named slot, see [`hide_slot_col`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `name` - Name of the slot.
+/// * `secret` - Secret text which will be hidden in the slot.
+///
+pub fn hide_slot_img(img_path: &str, name: &str, secret: &str, password: Option<SecretString>) -> Result<DynamicImage, String> {
+    let mut img = open_image(img_path)?;
+    insert_slot(&mut img, name, secret, password)?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and remove the named slot, see [`remove_slot_col`].
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the target image file.
+/// * `name` - Name of the slot which will be removed.
+///
+pub fn remove_slot_img(img_path: &str, name: &str) -> Result<DynamicImage, String> {
+    let mut img = open_image(img_path)?;
+    let mut table = SlotTable::read(&img);
+    table.remove(name)?;
+    table.write(&mut img)?;
+    Ok(img)
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and returns the names of all slots hidden in it.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for slots.
+///
+pub fn list_slots_img(img_path: &str) -> Result<Vec<String>, String> {
+    let img = open_image(img_path)?;
+    Ok(SlotTable::read(&img).names().into_iter().map(String::from).collect())
+}
+
+#[cfg(feature = "image")]
+/// Tries to load the target image and returns the secret of the named slot if it exists.
+///
+/// # Arguments
+///
+/// * `img_path` - Path to the image which will be searched for the slot.
+/// * `name` - Name of the slot.
+///
+pub fn find_slot_img(img_path: &str, name: &str, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let img = open_image(img_path)?;
+    Ok(SlotTable::read(&img).find(name, password))
+}
+
+/// Hides a secret in the named slot of a vector of pixels. An existing slot with the same name is replaced,
+/// all other slots are preserved. Secrets hidden with `hide_secret_col` are overwritten.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels the slot will be hidden in.
+/// * `name` - Name of the slot, at most 255 bytes.
+/// * `secret` - Secret text which will be hidden in the slot.
+///
+pub fn hide_slot_col(pixels: &mut [Color], name: &str, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    insert_slot(pixels, name, secret, password)
+}
+
+/// Removes the named slot from a vector of pixels, all other slots are preserved.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels carrying the slots.
+/// * `name` - Name of the slot which will be removed.
+///
+pub fn remove_slot_col(pixels: &mut [Color], name: &str) -> Result<(), String> {
+    let mut table = SlotTable::read(pixels);
+    table.remove(name)?;
+    table.write(pixels)
+}
+
+/// Returns the names of all slots hidden in a vector of pixels.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels which will be searched for slots.
+///
+pub fn list_slots_col(pixels: &[Color]) -> Vec<String> {
+    SlotTable::read(pixels).names().into_iter().map(String::from).collect()
+}
+
+/// Returns the secret of the named slot hidden in a vector of pixels if it exists.
+///
+/// # Arguments
+///
+/// * `pixels` - Vector of pixels which will be searched for the slot.
+/// * `name` - Name of the slot.
+///
+pub fn find_slot_col(pixels: &[Color], name: &str, password: Option<SecretString>) -> Option<SecretString> {
+    SlotTable::read(pixels).find(name, password)
+}
+
+fn insert_slot<C: Carrier + ?Sized>(carrier: &mut C, name: &str, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    let mut table = SlotTable::read(carrier);
+    table.insert(name, secret, password)?;
+    table.write(carrier)
+}
+
+#[cfg(feature = "image")]
+fn open_image(img_path: &str) -> Result<DynamicImage, String> {
+    image::open(img_path).map_err(|_| format!("Failed loading input image '{img_path}'"))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_table_ut() {
+        let mut table = SlotTable::new();
+        assert!(table.insert("message", "Lorem ipsum", None).is_ok());
+        assert!(table.insert("signature", "dolor sit amet", Some(SecretString::from("password"))).is_ok());
+        assert_eq!(vec!["message", "signature"], table.names());
+
+        // The table survives a round trip through its byte layout
+        assert_eq!(Some(table.to_owned()), SlotTable::from_bytes(&table.to_bytes()));
+        let bytes = table.to_bytes();
+        assert_eq!(None, SlotTable::from_bytes(&bytes[..bytes.len() - 1]));
+        assert_eq!(None, SlotTable::from_bytes(b"Lorem ipsum"));
+
+        // Replacing keeps the order of the slots
+        assert!(table.insert("message", "Replaced", None).is_ok());
+        assert_eq!(vec!["message", "signature"], table.names());
+        assert_eq!(Some(SecretString::from("Replaced")), table.find("message", None));
+        assert_eq!(Some(SecretString::from("dolor sit amet")), table.find("signature", Some(SecretString::from("password"))));
+
+        // Invalid names, empty secrets and unknown slots return Error/None
+        assert!(table.insert("", "Lorem ipsum", None).is_err());
+        assert!(table.insert(&"a".repeat(256), "Lorem ipsum", None).is_err());
+        assert!(table.insert("metadata", "", None).is_err());
+        assert!(table.remove("metadata").is_err());
+        assert_eq!(None, table.find("metadata", None));
+
+        // A full table only accepts replacements
+        let mut table = SlotTable {
+            slots: vec![(String::from("message"), SecretBytes::default()); u16::MAX as usize],
+        };
+        assert!(table.insert("signature", "Lorem ipsum", None).is_err());
+        assert!(table.insert("message", "Lorem ipsum", None).is_ok());
+        assert_eq!(u16::MAX as usize, table.names().len());
+    }
+
+    #[test]
+    fn hide_find_slot_col_ut() {
+        let mut pixels = vec![Color::new(); 400];
+        let password = SecretString::from("password");
+
+        // Pixels with no slots return an empty list
+        assert!(list_slots_col(&pixels).is_empty());
+        assert_eq!(None, find_slot_col(&pixels, "message", None));
+
+        // Successfully add several slots
+        assert!(hide_slot_col(&mut pixels, "message", "Lorem ipsum", None).is_ok());
+        assert!(hide_slot_col(&mut pixels, "signature", "dolor", Some(password.to_owned())).is_ok());
+        assert!(hide_slot_col(&mut pixels, "metadata", "sit amet", None).is_ok());
+        assert_eq!(vec!["message", "signature", "metadata"], list_slots_col(&pixels));
+
+        // Replacing and removing slots leaves the others untouched
+        assert!(hide_slot_col(&mut pixels, "message", "Replaced", None).is_ok());
+        assert!(remove_slot_col(&mut pixels, "metadata").is_ok());
+        assert_eq!(vec!["message", "signature"], list_slots_col(&pixels));
+        assert_eq!(Some(SecretString::from("Replaced")), find_slot_col(&pixels, "message", None));
+        assert_eq!(Some(SecretString::from("dolor")), find_slot_col(&pixels, "signature", Some(password)));
+        assert_eq!(None, find_slot_col(&pixels, "metadata", None));
+
+        // Removed slots are overwritten, their bytes are found neither in the table nor anywhere in the least significant bits
+        let hidden_anywhere = |pixels: &[Color], bytes: &[u8]| {
+            let samples: Vec<u8> = pixels.iter().flat_map(|p| [p.r, p.g, p.b]).collect();
+            (0..samples.len()).any(|start| extract(&samples[start..]).windows(bytes.len()).any(|window| window == bytes))
+        };
+        assert!(hide_slot_col(&mut pixels, "metadata", "sit amet", None).is_ok());
+        assert!(hidden_anywhere(&pixels, b"sit amet"));
+        assert!(remove_slot_col(&mut pixels, "metadata").is_ok());
+        assert!(!hidden_anywhere(&pixels, b"sit amet"));
+        assert!(!extract(pixels.as_slice()).windows(8).any(|window| window == b"sit amet"));
+
+        // Removing unknown slots or exceeding the capacity returns Error
+        assert!(remove_slot_col(&mut pixels, "metadata").is_err());
+        assert!(hide_slot_col(&mut pixels, "metadata", &"a".repeat(200), None).is_err());
+        assert_eq!(vec!["message", "signature"], list_slots_col(&pixels));
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_slot_img_ut() {
+        let path = std::env::temp_dir().join("hips_slots_ut.png");
+        let path = path.to_str().unwrap();
+
+        hide_slot_img("test_images/peppers.png", "message", "Lorem ipsum", None).unwrap().save(path).unwrap();
+        hide_slot_img(path, "signature", "dolor sit amet", None).unwrap().save(path).unwrap();
+        assert_eq!(Ok(vec![String::from("message"), String::from("signature")]), list_slots_img(path));
+        assert_eq!(Ok(Some(SecretString::from("Lorem ipsum"))), find_slot_img(path, "message", None));
+
+        remove_slot_img(path, "message").unwrap().save(path).unwrap();
+        assert_eq!(Ok(vec![String::from("signature")]), list_slots_img(path));
+        assert_eq!(Ok(Some(SecretString::from("dolor sit amet"))), find_slot_img(path, "signature", None));
+        std::fs::remove_file(path).unwrap();
+
+        // Images without slot table return an empty list, missing images return Error
+        assert_eq!(Ok(vec![]), list_slots_img("test_images/peppers.png"));
+        assert!(list_slots_img("test_images/non_existent_image").is_err());
+        assert!(hide_slot_img("test_images/non_existent_image", "message", "Lorem ipsum", None).is_err());
+    }
+}
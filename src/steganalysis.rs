@@ -0,0 +1,479 @@
+use crate::color::Color;
+
+#[cfg(feature = "image")]
+use image::{DynamicImage, GenericImageView};
+
+/// Minimum expected frequency of a pair of values to be included in the chi-square statistic.
+const MIN_EXPECTED: f64 = 5.0;
+
+/// Result of the chi-square attack on an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChiSquareReport {
+    /// Probability of an embedded payload considering all R, G and B values of the image.
+    pub probability: f64,
+    /// Probability of an embedded payload per channel (R, G, B).
+    pub channels: [f64; 3],
+    /// Probability of an embedded payload per window of consecutive pixels, in embedding order.
+    pub windows: Vec<f64>,
+}
+
+/// Histogram of the values of a single channel.
+type Histogram = [u64; 256];
+
+/// Performs the chi-square attack of Westfeld and Pfitzmann on a vector of pixels.
+///
+/// Replacing least significant bits with message bits equalizes the frequencies of each pair of values
+/// which only differ in the least significant bit (`2k` and `2k + 1`). The chi-square test compares the
+/// observed frequencies with the equalized frequencies, a probability close to one indicates an embedded payload.
+/// Pairs with an expected frequency below five are ignored.
+///
+/// # Arguments
+///
+/// * `pixels` - Pixels which will be analysed in embedding order.
+/// * `window` - Number of consecutive pixels per window, zero to skip the window analysis.
+///
+pub fn chi_square_col(pixels: &[Color], window: usize) -> ChiSquareReport {
+    chi_square(pixels.iter().map(|p| [p.r, p.g, p.b]), window)
+}
+
+#[cfg(feature = "image")]
+/// Performs the chi-square attack of Westfeld and Pfitzmann on an image, see [`chi_square_col`].
+///
+/// # Arguments
+///
+/// * `img` - Image which will be analysed row by row.
+/// * `window` - Number of consecutive pixels per window, zero to skip the window analysis.
+///
+pub fn chi_square_img(img: &DynamicImage, window: usize) -> ChiSquareReport {
+    chi_square(img.pixels().map(|(_, _, p)| [p[0], p[1], p[2]]), window)
+}
+
+/// Returns the probability of an embedded payload in a sequence of samples.
+///
+/// # Arguments
+///
+/// * `samples` - Values of a single channel.
+///
+pub fn chi_square_probability<I: IntoIterator<Item = u8>>(samples: I) -> f64 {
+    let mut histogram = [0; 256];
+    for sample in samples {
+        histogram[sample as usize] += 1;
+    }
+
+    probability(&[histogram])
+}
+
+fn chi_square<I: Iterator<Item = [u8; 3]>>(pixels: I, window: usize) -> ChiSquareReport {
+    let mut channels: [Histogram; 3] = [[0; 256]; 3];
+    let mut current: [Histogram; 3] = [[0; 256]; 3];
+    let mut windows = vec![];
+    let mut window_len = 0;
+
+    for pixel in pixels {
+        for (channel, value) in pixel.iter().enumerate() {
+            channels[channel][*value as usize] += 1;
+            current[channel][*value as usize] += 1;
+        }
+
+        window_len += 1;
+        if window_len == window {
+            windows.push(probability(&current));
+            current = [[0; 256]; 3];
+            window_len = 0;
+        }
+    }
+
+    if window > 0 && window_len > 0 {
+        windows.push(probability(&current));
+    }
+
+    ChiSquareReport {
+        probability: probability(&channels),
+        channels: [probability(&channels[..1]), probability(&channels[1..2]), probability(&channels[2..])],
+        windows,
+    }
+}
+
+/// Estimates the fraction of least significant bits replaced by a payload per channel (R, G, B)
+/// using the RS analysis of Fridrich, Goljan and Du.
+///
+/// Groups of four consecutive values are classified as regular or singular depending on whether flipping
+/// their least significant bits increases or decreases their noise. Embedding moves the classes towards
+/// each other at a known rate, so the payload length can be recovered from a quadratic equation.
+/// About half of the replaced bits actually change their value.
+///
+/// # Arguments
+///
+/// * `pixels` - Pixels which will be analysed in embedding order.
+///
+pub fn rs_analysis_col(pixels: &[Color]) -> [f64; 3] {
+    let samples = channel_samples(pixels.iter().map(|p| [p.r, p.g, p.b]));
+    samples.map(|channel| rs_estimate(&channel, pixels.len()))
+}
+
+#[cfg(feature = "image")]
+/// Estimates the fraction of least significant bits replaced by a payload per channel (R, G, B)
+/// using the RS analysis, see [`rs_analysis_col`]. Groups never span more than one row.
+///
+/// # Arguments
+///
+/// * `img` - Image which will be analysed.
+///
+pub fn rs_analysis_img(img: &DynamicImage) -> [f64; 3] {
+    let samples = channel_samples(img.pixels().map(|(_, _, p)| [p[0], p[1], p[2]]));
+    samples.map(|channel| rs_estimate(&channel, img.width() as usize))
+}
+
+/// Estimates the fraction of least significant bits replaced by a payload per channel (R, G, B)
+/// using the sample pair analysis of Dumitrescu, Wu and Wang.
+///
+/// Pairs of consecutive values are divided into trace sets whose sizes are changed by embedding
+/// in a predictable way. About half of the replaced bits actually change their value.
+///
+/// # Arguments
+///
+/// * `pixels` - Pixels which will be analysed in embedding order.
+///
+pub fn sample_pair_analysis_col(pixels: &[Color]) -> [f64; 3] {
+    let samples = channel_samples(pixels.iter().map(|p| [p.r, p.g, p.b]));
+    samples.map(|channel| sample_pair_estimate(&channel, pixels.len()))
+}
+
+#[cfg(feature = "image")]
+/// Estimates the fraction of least significant bits replaced by a payload per channel (R, G, B)
+/// using the sample pair analysis, see [`sample_pair_analysis_col`]. Pairs never span more than one row.
+///
+/// # Arguments
+///
+/// * `img` - Image which will be analysed.
+///
+pub fn sample_pair_analysis_img(img: &DynamicImage) -> [f64; 3] {
+    let samples = channel_samples(img.pixels().map(|(_, _, p)| [p[0], p[1], p[2]]));
+    samples.map(|channel| sample_pair_estimate(&channel, img.width() as usize))
+}
+
+/// Splits pixels into the values of the R, G and B channel.
+fn channel_samples<I: Iterator<Item = [u8; 3]>>(pixels: I) -> [Vec<u8>; 3] {
+    let mut channels = [vec![], vec![], vec![]];
+    for pixel in pixels {
+        for (channel, value) in pixel.iter().enumerate() {
+            channels[channel].push(*value);
+        }
+    }
+    channels
+}
+
+/// Returns the RS estimate of the embedding rate of a single channel.
+fn rs_estimate(samples: &[u8], row_len: usize) -> f64 {
+    /// Flipping mask applied to every group of values.
+    const MASK: [bool; 4] = [false, true, true, false];
+
+    // Noise of a group, the sum of the differences of neighbouring values
+    let noise = |group: &[i32]| group.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<i32>();
+    // Positive flipping swaps 2k and 2k + 1, negative flipping swaps 2k - 1 and 2k
+    let flip = |group: &[i32], negative: bool| -> Vec<i32> {
+        group
+            .iter()
+            .zip(MASK)
+            .map(|(value, masked)| match (masked, negative) {
+                (false, _) => *value,
+                (true, false) => value ^ 1,
+                (true, true) => ((value + 1) ^ 1) - 1,
+            })
+            .collect()
+    };
+
+    // Differences of regular and singular groups for the mask and the negative mask,
+    // for the samples as given and with all least significant bits flipped
+    let mut differences = [[0.0; 2]; 2];
+    let mut groups = 0;
+    for group in samples.chunks(row_len.max(1)).flat_map(|row| row.chunks_exact(MASK.len())) {
+        let group: Vec<i32> = group.iter().map(|value| *value as i32).collect();
+        let flipped: Vec<i32> = group.iter().map(|value| value ^ 1).collect();
+
+        for (idx, group) in [group, flipped].iter().enumerate() {
+            let original = noise(group);
+            for (negative, difference) in [false, true].into_iter().zip(differences[idx].iter_mut()) {
+                *difference += (noise(&flip(group, negative)) - original).signum() as f64;
+            }
+        }
+        groups += 1;
+    }
+
+    if groups == 0 {
+        return 0.0;
+    }
+
+    let [[d0, dm0], [d1, dm1]] = differences.map(|pair| pair.map(|difference| difference / groups as f64));
+
+    // 2 (d1 + d0) z^2 + (dm0 - dm1 - d1 - 3 d0) z + d0 - dm0 = 0
+    let root = smaller_root(2.0 * (d1 + d0), dm0 - dm1 - d1 - 3.0 * d0, d0 - dm0);
+    (root / (root - 0.5)).clamp(0.0, 1.0)
+}
+
+/// Returns the sample pair estimate of the embedding rate of a single channel.
+fn sample_pair_estimate(samples: &[u8], row_len: usize) -> f64 {
+    let (mut x, mut y, mut close, mut pairs) = (0.0, 0.0, 0.0, 0.0);
+
+    for pair in samples.chunks(row_len.max(1)).flat_map(|row| row.windows(2)) {
+        let (u, v) = (pair[0], pair[1]);
+        if (v % 2 == 0 && u < v) || (v % 2 == 1 && u > v) {
+            x += 1.0;
+        }
+        if (v % 2 == 0 && u > v) || (v % 2 == 1 && u < v) {
+            y += 1.0;
+        }
+        // Pairs which only differ in the least significant bit
+        if u / 2 == v / 2 {
+            close += 1.0;
+        }
+        pairs += 1.0;
+    }
+
+    if pairs == 0.0 {
+        return 0.0;
+    }
+
+    // The smaller root is the fraction of changed values, half of the embedding rate
+    (2.0 * smaller_root(2.0 * close, 2.0 * (2.0 * x - pairs), y - x)).clamp(0.0, 1.0)
+}
+
+/// Returns the root of `a x^2 + b x + c` with the smaller absolute value, the real part for complex roots.
+fn smaller_root(a: f64, b: f64, c: f64) -> f64 {
+    if a == 0.0 {
+        return if b == 0.0 { 0.0 } else { -c / b };
+    }
+
+    let discriminant = (b * b - 4.0 * a * c).max(0.0).sqrt();
+    let roots = [(-b + discriminant) / (2.0 * a), (-b - discriminant) / (2.0 * a)];
+    if roots[0].abs() < roots[1].abs() {
+        roots[0]
+    } else {
+        roots[1]
+    }
+}
+
+/// Returns the probability of equalized pairs of values across the given histograms.
+///
+/// The expected share of odd values in every pair is the share of odd values within the histogram rather
+/// than one half, so payloads whose bits are not uniformly distributed (e.g. unencrypted text) are detected as well.
+fn probability(histograms: &[Histogram]) -> f64 {
+    let mut chi_square = 0.0;
+    let mut categories = 0;
+
+    for histogram in histograms {
+        let pairs: Vec<(f64, f64)> = histogram
+            .chunks(2)
+            .map(|pair| ((pair[0] + pair[1]) as f64, pair[1] as f64))
+            .filter(|(total, _)| *total / 2.0 >= MIN_EXPECTED)
+            .collect();
+
+        let total: f64 = pairs.iter().map(|(total, _)| total).sum();
+        let odd_share = pairs.iter().map(|(_, odd)| odd).sum::<f64>() / total;
+        if pairs.is_empty() || odd_share <= 0.0 || odd_share >= 1.0 {
+            continue;
+        }
+
+        for (total, odd) in pairs {
+            // Reduces to the statistic of the original attack, (odd - total / 2)^2 / (total / 2), for an odd share of one half
+            chi_square += (odd - total * odd_share).powi(2) / (2.0 * total * odd_share * (1.0 - odd_share));
+            categories += 1;
+        }
+        // The odd share is estimated from the histogram itself
+        categories -= 1;
+    }
+
+    if categories < 1 {
+        return 0.0;
+    }
+
+    // Probability of a chi-square statistic at least this large
+    upper_incomplete_gamma(categories as f64 / 2.0, chi_square / 2.0)
+}
+
+/// Returns the natural logarithm of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [76.18009172947146, -86.50532032941677, 24.01409824083091, -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5];
+
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series = COEFFICIENTS.iter().enumerate().fold(1.000000000190015, |sum, (idx, c)| sum + c / (x + 1.0 + idx as f64));
+
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Returns the regularized upper incomplete gamma function Q(a, x).
+fn upper_incomplete_gamma(a: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-12;
+    const ITERATIONS: usize = 1000;
+
+    if x <= 0.0 {
+        return 1.0;
+    }
+
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        // Series representation of the lower incomplete gamma function
+        let (mut term, mut sum) = (1.0 / a, 1.0 / a);
+        for n in 1..ITERATIONS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * prefix).clamp(0.0, 1.0)
+    } else {
+        // Continued fraction representation (modified Lentz)
+        let tiny = f64::MIN_POSITIVE / EPSILON;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for n in 1..ITERATIONS {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            d = if d.abs() < tiny { 1.0 / tiny } else { 1.0 / d };
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (prefix * h).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::carrier::embed;
+
+    /// Returns pseudo random bytes from a linear congruential generator.
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    /// Returns pixels of a smooth gradient quantized to multiples of three, so pairs of values are uneven.
+    fn cover(len: usize) -> Vec<Color> {
+        (0..len)
+            .map(|n| ((n as f64 / 37.0).sin() * 60.0 + 128.0) as u8 / 3 * 3)
+            .map(|v| Color::from_rgb(v, v / 2 / 3 * 3 + 21, 255 - v))
+            .collect()
+    }
+
+    #[test]
+    fn upper_incomplete_gamma_ut() {
+        // Chi-square distribution with two degrees of freedom: Q(1, x / 2) = exp(-x / 2)
+        for x in [0.5, 1.0, 4.0, 10.0] {
+            assert!((upper_incomplete_gamma(1.0, x / 2.0) - (-x / 2.0f64).exp()).abs() < 1e-9);
+        }
+
+        // Critical value of the chi-square distribution with 10 degrees of freedom at 5%
+        assert!((upper_incomplete_gamma(5.0, 18.307 / 2.0) - 0.05).abs() < 1e-4);
+        assert_eq!(1.0, upper_incomplete_gamma(5.0, 0.0));
+    }
+
+    #[test]
+    fn chi_square_col_ut() {
+        let mut pixels = cover(30000);
+
+        // The cover is not flagged
+        let report = chi_square_col(&pixels, 3000);
+        assert!(report.probability < 0.01);
+        assert!(report.windows.iter().all(|p| *p < 0.01));
+
+        // Random payload in the first half is flagged, the second half is clean
+        let payload = noise(30000 * 3 / 2 / 9, 42);
+        assert!(embed(pixels.as_mut_slice(), &payload).is_ok());
+
+        let report = chi_square_col(&pixels[..15000], 0);
+        assert!(report.probability > 0.95);
+        assert!(report.channels.iter().all(|p| *p > 0.5));
+        assert!(report.windows.is_empty());
+
+        let report = chi_square_col(&pixels, 3000);
+        assert_eq!(10, report.windows.len());
+        assert!(report.windows[..5].iter().all(|p| *p > 0.95));
+        assert!(report.windows[5..].iter().all(|p| *p < 0.01));
+
+        // Empty input is not flagged
+        assert_eq!(0.0, chi_square_col(&[], 10).probability);
+        assert_eq!(0.0, chi_square_probability(vec![]));
+    }
+
+    #[test]
+    fn rs_sample_pair_analysis_col_ut() {
+        // Smooth gradient with a little noise
+        let cover: Vec<Color> = noise(30000, 3)
+            .iter()
+            .enumerate()
+            .map(|(n, jitter)| ((n as f64 / 37.0).sin() * 60.0 + 128.0) as u8 + jitter % 5)
+            .map(|v| Color::from_rgb(v, v / 2 + 20, 255 - v))
+            .collect();
+
+        let mut pixels = cover.clone();
+        let payload = noise(30000 * 3 / 2 / 9, 42);
+        assert!(embed(pixels.as_mut_slice(), &payload).is_ok());
+
+        // The payload covers half of the samples
+        for analysis in [rs_analysis_col, sample_pair_analysis_col] {
+            assert!(analysis(&cover).iter().all(|rate| *rate < 0.05));
+            assert!(analysis(&pixels).iter().all(|rate| (rate - 0.5).abs() < 0.1));
+        }
+
+        // Empty input has no payload
+        assert_eq!([0.0; 3], rs_analysis_col(&[]));
+        assert_eq!([0.0; 3], sample_pair_analysis_col(&[]));
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn rs_sample_pair_analysis_img_ut() {
+        use crate::carrier::Carrier;
+
+        let cover = image::open("test_images/girl_with_a_pearl_earring.jpg").unwrap();
+        let mut img = cover.clone();
+        let payload = noise(img.sample_count() / 2 / 9, 42);
+        assert!(embed(&mut img, &payload).is_ok());
+
+        for analysis in [rs_analysis_img, sample_pair_analysis_img] {
+            let (cover_rates, rates) = (analysis(&cover), analysis(&img));
+
+            // The green channel of the cover is almost free of compression artifacts
+            assert!(cover_rates[1] < 0.05);
+            assert!((rates[1] - 0.5).abs() < 0.1);
+            assert!(cover_rates.iter().zip(rates).all(|(cover_rate, rate)| rate > cover_rate + 0.2));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn chi_square_img_ut() {
+        use crate::hips::hide_secret_img;
+        use crate::secret::SecretString;
+
+        // The cover is not flagged
+        let img = image::open("test_images/peppers.png").unwrap();
+        let report = chi_square_img(&img, 0);
+        assert!(report.probability < 0.01);
+
+        // An image filled by `hide_secret_img` is flagged in the windows carrying the secret
+        let secret: String = (0..6000).map(|n| char::from(b'!' + (n * 7919 % 94) as u8)).collect();
+        let img = hide_secret_img("test_images/peppers.png", &secret, Some(SecretString::from("password"))).unwrap();
+        let report = chi_square_img(&img, 4000);
+        assert!(report.windows[0] > 0.95);
+        assert!(report.windows[report.windows.len() - 1] < 0.01);
+    }
+}
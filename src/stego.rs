@@ -0,0 +1,1089 @@
+use std::io::{self, Read, Write};
+
+use crate::bit_ops::BitOps;
+use crate::carrier::Carrier;
+use crate::color::Channel;
+
+#[cfg(feature = "crypto")]
+use crate::crypto::{derive_key, open, random_bytes, seal, Permutation, SALT_LEN};
+#[cfg(feature = "crypto")]
+use crate::secret::{SecretBytes, SecretString};
+
+/// Context of the keys derived from the key choosing the sample order.
+#[cfg(feature = "crypto")]
+const ORDER_CONTEXT: &str = "hips-lib stego order";
+
+/// Context of the keys derived from the encryption password.
+#[cfg(feature = "crypto")]
+const ENCRYPTION_CONTEXT: &str = "hips-lib stego encryption";
+
+/// Number of bits of the length in front of the payload when using [`Header::Length`].
+const LENGTH_BITS: usize = 32;
+
+/// Upper limit of the size of decompressed payloads, so tampered payloads cannot exhaust the memory.
+#[cfg(feature = "compression")]
+const MAX_DECOMPRESSED_LEN: usize = 1 << 28;
+
+/// How the bits of the payload are written to the samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The bits replace the least significant bits of the samples.
+    #[default]
+    Replacement,
+    /// Samples whose least significant bit differs from the payload bit are incremented or decremented at random.
+    /// This avoids the pairs of values typical for replacement which are detected by steganalysis.
+    /// Only supported with a depth of one bit.
+    Matching,
+}
+
+/// How the end of the payload is marked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Header {
+    /// Every byte is followed by a flag which is set if more bytes follow, the format of [`crate::carrier::embed`].
+    #[default]
+    Terminated,
+    /// The payload is preceded by its length as 32 bit number, which needs less space for payloads longer than four bytes.
+    Length,
+}
+
+/// Error correction applied to the hidden bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ecc {
+    #[default]
+    None,
+    /// Every four bits are stored as Hamming(7,4) code word, which corrects a single flipped bit in every seven bits.
+    Hamming,
+}
+
+/// Reusable configuration of how payloads are hidden in and retrieved from carriers.
+///
+/// The default configuration hides payloads in the least significant bits of all samples in order,
+/// with the layout of [`crate::carrier::embed`].
+/// Payloads have to be retrieved with the same configuration they were hidden with.
+#[derive(Clone, Debug)]
+pub struct Stego {
+    algorithm: Algorithm,
+    /// Sorted positions of the used channels within every group of three samples.
+    channels: Vec<usize>,
+    depth: u8,
+    /// Key choosing the sample order, derived once from the key set on the builder.
+    #[cfg(feature = "crypto")]
+    key: Option<SecretBytes>,
+    #[cfg(feature = "crypto")]
+    encryption: Option<SecretString>,
+    #[cfg(feature = "compression")]
+    compression: u8,
+    ecc: Ecc,
+    header: Header,
+}
+
+/// Builder of a [`Stego`] configuration, created by [`Stego::builder`].
+#[derive(Clone, Debug)]
+pub struct StegoBuilder {
+    stego: Stego,
+    channels: Vec<Channel>,
+    #[cfg(feature = "crypto")]
+    key: Option<SecretString>,
+}
+
+/// Reads a payload hidden with a [`Stego`] configuration byte by byte, created by [`Stego::reader`].
+///
+/// Samples are only read when the next byte is requested, so reading stops as soon as the end of the payload
+/// is reached instead of visiting the whole carrier.
+pub struct StegoReader<'a> {
+    bits: Box<dyn Iterator<Item = bool> + 'a>,
+    /// Number of bytes which are still to be read with [`Header::Length`].
+    remaining: Option<usize>,
+    finished: bool,
+}
+
+/// Hides a payload with a [`Stego`] configuration byte by byte, created by [`Stego::writer`].
+///
+/// Bytes are written to the carrier as they arrive, so large payloads are never held in memory as a whole.
+/// The payload is only complete after [`StegoWriter::finish`], which writes the end marker or the length.
+pub struct StegoWriter<'a, C: Carrier + ?Sized> {
+    stego: &'a Stego,
+    carrier: &'a mut C,
+    order: Order,
+    /// Number of payload bytes which fit in the carrier.
+    capacity: usize,
+    /// Number of payload bytes received so far.
+    len: usize,
+    /// Number of hidden bits written so far, including the space reserved for the length.
+    position: usize,
+    /// Last byte with [`Header::Terminated`], written as soon as it is known whether more bytes follow.
+    pending_byte: Option<u8>,
+    /// Bits waiting for a complete Hamming code word, the first bit being the lowest, and their number.
+    pending_bits: u8,
+    pending_len: usize,
+    /// Bits waiting for the current sample with [`Algorithm::Replacement`], the first bit being the lowest, and their number.
+    field: u8,
+    field_len: u32,
+    /// Random bits deciding the direction of changes with [`Algorithm::Matching`].
+    random: [u8; 32],
+    /// Number of random bits which were already used.
+    random_used: usize,
+}
+
+/// Order in which the usable samples of a carrier are visited.
+enum Order {
+    /// All usable samples in their natural order.
+    Sequential(usize),
+    /// The start of a key dependent permutation of the usable samples and its length.
+    #[cfg(feature = "crypto")]
+    Shuffled(Permutation, usize),
+}
+
+impl Order {
+    fn len(&self) -> usize {
+        match self {
+            Order::Sequential(len) => *len,
+            #[cfg(feature = "crypto")]
+            Order::Shuffled(_, len) => *len,
+        }
+    }
+
+    fn get(&self, n: usize) -> usize {
+        match self {
+            Order::Sequential(_) => n,
+            #[cfg(feature = "crypto")]
+            Order::Shuffled(permutation, _) => permutation.get(n),
+        }
+    }
+}
+
+impl Default for Stego {
+    fn default() -> Self {
+        Stego {
+            algorithm: Algorithm::default(),
+            channels: vec![0, 1, 2],
+            depth: 1,
+            #[cfg(feature = "crypto")]
+            key: None,
+            #[cfg(feature = "crypto")]
+            encryption: None,
+            #[cfg(feature = "compression")]
+            compression: 0,
+            ecc: Ecc::default(),
+            header: Header::default(),
+        }
+    }
+}
+
+impl Default for StegoBuilder {
+    fn default() -> Self {
+        StegoBuilder {
+            stego: Stego::default(),
+            channels: vec![Channel::Red, Channel::Green, Channel::Blue],
+            #[cfg(feature = "crypto")]
+            key: None,
+        }
+    }
+}
+
+impl StegoBuilder {
+    /// Sets how the bits are written to the samples, [`Algorithm::Replacement`] by default.
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.stego.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the channels which carry hidden bits, all of red, green and blue by default.
+    /// Samples of a carrier are grouped in threes, e.g. the R, G and B values of a pixel. Alpha values are never used.
+    pub fn channels(mut self, channels: &[Channel]) -> Self {
+        self.channels = channels.to_vec();
+        self
+    }
+
+    /// Sets the number of low bits of every sample which carry hidden bits, between 1 and 8. One by default.
+    pub fn depth(mut self, depth: u8) -> Self {
+        self.stego.depth = depth;
+        self
+    }
+
+    /// Sets a key which spreads the bits over key dependent positions instead of writing them in order.
+    #[cfg(feature = "crypto")]
+    pub fn key(mut self, key: impl Into<SecretString>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Encrypts and authenticates payloads with ChaCha20-Poly1305 and a key derived from the password
+    /// and a random salt, which is stored in front of the payload.
+    #[cfg(feature = "crypto")]
+    pub fn encryption(mut self, password: impl Into<SecretString>) -> Self {
+        self.stego.encryption = Some(password.into());
+        self
+    }
+
+    /// Compresses payloads with Deflate before they are hidden. The level is between 1 and 10, 0 disables compression.
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, level: u8) -> Self {
+        self.stego.compression = level;
+        self
+    }
+
+    /// Sets the error correction applied to the hidden bits, none by default.
+    pub fn ecc(mut self, ecc: Ecc) -> Self {
+        self.stego.ecc = ecc;
+        self
+    }
+
+    /// Sets how the end of the payload is marked, [`Header::Terminated`] by default.
+    pub fn header(mut self, header: Header) -> Self {
+        self.stego.header = header;
+        self
+    }
+
+    /// Returns the configuration, or Error if the options do not fit together.
+    pub fn build(self) -> Result<Stego, String> {
+        let mut stego = self.stego;
+
+        if !(1..=8).contains(&stego.depth) {
+            return Err(format!("The depth {} is invalid. Try using a depth between 1 and 8 bits.", stego.depth));
+        }
+        if stego.algorithm == Algorithm::Matching && stego.depth > 1 {
+            return Err(String::from("Matching only supports a depth of one bit. Try using replacement for larger depths."));
+        }
+        if self.channels.is_empty() || self.channels.contains(&Channel::Alpha) {
+            return Err(String::from("The channels are invalid. Try using at least one of red, green and blue."));
+        }
+        #[cfg(feature = "crypto")]
+        if [&self.key, &stego.encryption].iter().any(|secret| secret.as_ref().is_some_and(|secret| secret.is_empty())) {
+            return Err(String::from("You have entered an empty key or password. Try to use at least one character."));
+        }
+        #[cfg(feature = "compression")]
+        if stego.compression > 10 {
+            return Err(format!("The compression level {} is invalid. Try using a level between 0 and 10.", stego.compression));
+        }
+
+        stego.channels = self.channels.iter().map(|channel| channel.index()).collect();
+        stego.channels.sort_unstable();
+        stego.channels.dedup();
+
+        // The order has to be known before anything is read, so its key cannot be salted
+        #[cfg(feature = "crypto")]
+        {
+            stego.key = self.key.map(|key| SecretBytes::from(derive_key(&key, ORDER_CONTEXT, &[]).as_slice()));
+        }
+        Ok(stego)
+    }
+}
+
+impl Stego {
+    /// Returns a builder starting from the default configuration.
+    pub fn builder() -> StegoBuilder {
+        StegoBuilder::default()
+    }
+
+    /// Returns the number of payload bytes which can be hidden in the carrier, not counting compression.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier the payload would be hidden in.
+    ///
+    pub fn capacity<C: Carrier + ?Sized>(&self, carrier: &C) -> usize {
+        let bits = self.usable_samples(carrier) * self.depth as usize;
+        let bits = match self.ecc {
+            Ecc::None => bits,
+            Ecc::Hamming => bits / 7 * 4,
+        };
+        let bytes = match self.header {
+            Header::Terminated => bits / 9,
+            Header::Length => bits.saturating_sub(LENGTH_BITS) / 8,
+        };
+        bytes.saturating_sub(self.overhead())
+    }
+
+    /// Hides the payload in the carrier. Returns the number of samples which were written.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier the payload will be hidden in.
+    /// * `payload` - Bytes which will be hidden.
+    ///
+    pub fn hide<C: Carrier + ?Sized>(&self, carrier: &mut C, payload: &[u8]) -> Result<usize, String> {
+        if payload.is_empty() {
+            return Err(String::from("There is nothing to hide. Try to provide at least one byte."));
+        }
+
+        #[cfg(feature = "compression")]
+        let compressed;
+        #[cfg(feature = "compression")]
+        let payload = if self.compression > 0 {
+            compressed = miniz_oxide::deflate::compress_to_vec(payload, self.compression);
+            compressed.as_slice()
+        } else {
+            payload
+        };
+
+        #[cfg(feature = "crypto")]
+        let sealed;
+        #[cfg(feature = "crypto")]
+        let payload = match &self.encryption {
+            Some(password) => {
+                let salt = random_bytes(SALT_LEN)?;
+                sealed = [salt.as_slice(), &seal(&derive_key(password, ENCRYPTION_CONTEXT, &salt), payload)?].concat();
+                sealed.as_slice()
+            }
+            None => payload,
+        };
+
+        // The payload already contains the overhead of the encryption
+        if payload.len() > self.capacity(carrier) + self.overhead() {
+            return Err(String::from("The data is too long to be hidden in this carrier. Try using less data or a larger carrier."));
+        }
+
+        let bits = match self.header {
+            Header::Terminated => payload.len() * 9,
+            Header::Length => LENGTH_BITS + payload.len() * 8,
+        };
+        let mut writer = self.raw_writer(carrier, self.raw_len(bits).div_ceil(self.depth as usize));
+        payload.iter().try_for_each(|byte| writer.write_byte(*byte))?;
+        writer.finish()
+    }
+
+    /// Returns a writer hiding a payload in the carrier while it is written. The payload is complete after
+    /// [`StegoWriter::finish`] was called.
+    ///
+    /// Encrypted or compressed payloads can only be hidden as a whole with [`Stego::hide`].
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier the payload will be hidden in.
+    ///
+    pub fn writer<'a, C: Carrier + ?Sized>(&'a self, carrier: &'a mut C) -> Result<StegoWriter<'a, C>, String> {
+        if self.overhead() > 0 || self.is_compressed() {
+            return Err(String::from("Encrypted or compressed payloads cannot be written as stream. Try using hide instead."));
+        }
+
+        let samples = self.usable_samples(carrier);
+        Ok(self.raw_writer(carrier, samples))
+    }
+
+    /// Returns a writer visiting the first `samples` usable samples.
+    fn raw_writer<'a, C: Carrier + ?Sized>(&'a self, carrier: &'a mut C, samples: usize) -> StegoWriter<'a, C> {
+        StegoWriter {
+            stego: self,
+            order: self.order(carrier, samples),
+            capacity: self.capacity(carrier) + self.overhead(),
+            len: 0,
+            position: match self.header {
+                Header::Terminated => 0,
+                Header::Length => self.raw_len(LENGTH_BITS),
+            },
+            carrier,
+            pending_byte: None,
+            pending_bits: 0,
+            pending_len: 0,
+            field: 0,
+            field_len: 0,
+            random: [0; 32],
+            random_used: 256,
+        }
+    }
+
+    /// Returns the payload hidden in the carrier.
+    /// With [`Header::Terminated`] all complete bytes are returned if the carrier runs out of samples,
+    /// otherwise Error is returned if the payload is incomplete or cannot be decrypted or decompressed.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier which will be searched for a payload.
+    ///
+    pub fn extract<C: Carrier + ?Sized>(&self, carrier: &C) -> Result<Vec<u8>, String> {
+        let mut reader = self.raw_reader(carrier)?;
+        let payload = std::iter::from_fn(|| reader.next_byte().transpose()).collect::<Result<Vec<u8>, String>>()?;
+
+        #[cfg(feature = "crypto")]
+        let payload = match &self.encryption {
+            Some(password) => {
+                let (salt, sealed) = payload.split_at(SALT_LEN.min(payload.len()));
+                open(&derive_key(password, ENCRYPTION_CONTEXT, salt), sealed).ok_or_else(|| String::from("Failed decrypting the payload. Try using the password it was hidden with."))?
+            }
+            None => payload,
+        };
+
+        #[cfg(feature = "compression")]
+        let payload = match self.compression {
+            0 => payload,
+            _ => miniz_oxide::inflate::decompress_to_vec_with_limit(&payload, MAX_DECOMPRESSED_LEN)
+                .map_err(|_| String::from("Failed decompressing the payload. Try using the configuration it was hidden with."))?,
+        };
+
+        Ok(payload)
+    }
+
+    /// Returns a reader over the payload hidden in the carrier which only visits as many samples as needed.
+    /// With [`Header::Length`] the length is read right away and Error is returned if it does not fit in the carrier.
+    ///
+    /// Encrypted or compressed payloads can only be retrieved as a whole with [`Stego::extract`].
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier which will be searched for a payload.
+    ///
+    pub fn reader<'a, C: Carrier + ?Sized>(&'a self, carrier: &'a C) -> Result<StegoReader<'a>, String> {
+        if self.overhead() > 0 || self.is_compressed() {
+            return Err(String::from("Encrypted or compressed payloads cannot be read as stream. Try using extract instead."));
+        }
+
+        self.raw_reader(carrier)
+    }
+
+    fn raw_reader<'a, C: Carrier + ?Sized>(&'a self, carrier: &'a C) -> Result<StegoReader<'a>, String> {
+        match self.header {
+            Header::Terminated => Ok(StegoReader {
+                bits: self.read_bits(carrier, self.order(carrier, self.usable_samples(carrier))),
+                remaining: None,
+                finished: false,
+            }),
+            Header::Length => {
+                let order = self.order(carrier, self.raw_len(LENGTH_BITS).div_ceil(self.depth as usize));
+                let mut bits = self.read_bits(carrier, order);
+                let length = (0..LENGTH_BITS / 8).map(|_| read_byte(&mut bits)).collect::<Option<Vec<u8>>>().ok_or_else(incomplete)?;
+                let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+                if length > self.capacity(carrier) + self.overhead() {
+                    return Err(incomplete());
+                }
+
+                let order = self.order(carrier, self.raw_len(LENGTH_BITS + length * 8).div_ceil(self.depth as usize));
+                Ok(StegoReader {
+                    bits: Box::new(self.read_bits(carrier, order).skip(LENGTH_BITS)),
+                    remaining: Some(length),
+                    finished: false,
+                })
+            }
+        }
+    }
+
+    /// Returns whether payloads are compressed before they are hidden.
+    fn is_compressed(&self) -> bool {
+        #[cfg(feature = "compression")]
+        if self.compression > 0 {
+            return true;
+        }
+        false
+    }
+
+    /// Returns the number of bytes added to every payload by encryption.
+    fn overhead(&self) -> usize {
+        #[cfg(feature = "crypto")]
+        if self.encryption.is_some() {
+            return SALT_LEN + crate::crypto::NONCE_LEN + crate::crypto::TAG_LEN;
+        }
+        0
+    }
+
+    /// Returns the number of samples of the carrier in the used channels.
+    fn usable_samples<C: Carrier + ?Sized>(&self, carrier: &C) -> usize {
+        let count = carrier.sample_count();
+        count / 3 * self.channels.len() + self.channels.iter().filter(|channel| **channel < count % 3).count()
+    }
+
+    /// Returns the position in the carrier of the n-th usable sample.
+    fn sample_idx(&self, n: usize) -> usize {
+        n / self.channels.len() * 3 + self.channels[n % self.channels.len()]
+    }
+
+    /// Returns the order of the first `len` usable samples which are visited.
+    #[cfg_attr(not(feature = "crypto"), allow(unused_variables))]
+    fn order<C: Carrier + ?Sized>(&self, carrier: &C, len: usize) -> Order {
+        let usable = self.usable_samples(carrier);
+
+        #[cfg(feature = "crypto")]
+        if let Some(key) = &self.key {
+            return Order::Shuffled(Permutation::new(key.as_ref().try_into().unwrap(), "samples", usable), len.min(usable));
+        }
+
+        Order::Sequential(usable)
+    }
+
+    /// Returns the number of hidden bits needed for the given number of payload bits.
+    fn raw_len(&self, bits: usize) -> usize {
+        match self.ecc {
+            Ecc::None => bits,
+            Ecc::Hamming => bits.div_ceil(4) * 7,
+        }
+    }
+
+    /// Returns the hidden bits in the given order with the error correction removed.
+    fn read_bits<'a, C: Carrier + ?Sized>(&'a self, carrier: &'a C, order: Order) -> Box<dyn Iterator<Item = bool> + 'a> {
+        let depth = self.depth as u32;
+        let mut fields = (0..order.len()).map(move |n| carrier.sample(self.sample_idx(order.get(n))).get_bits(0, depth));
+
+        // The low bits of the samples are collected until a unit is complete, the bits of a sample or a Hamming code word
+        let (unit_len, data_len) = match self.ecc {
+            Ecc::None => (depth, depth),
+            Ecc::Hamming => (7, 4),
+        };
+        let (mut buffer, mut buffered) = (0u16, 0);
+        let units = std::iter::from_fn(move || {
+            while buffered < unit_len {
+                buffer = buffer.set_bits(buffered, depth, fields.next()? as u16);
+                buffered += depth;
+            }
+            let unit = buffer.get_bits(0, unit_len) as u8;
+            (buffer, buffered) = (buffer >> unit_len, buffered - unit_len);
+            Some(unit)
+        });
+
+        let data: Box<dyn Iterator<Item = u8> + 'a> = match self.ecc {
+            Ecc::None => Box::new(units),
+            Ecc::Hamming => Box::new(units.map(hamming_decode)),
+        };
+        Box::new(data.flat_map(move |data| (0..data_len).map(move |idx| data.get_bit(idx))))
+    }
+}
+
+impl StegoReader<'_> {
+    /// Returns the next byte of the payload, or None after the last byte.
+    /// With [`Header::Terminated`] reading ends silently if the carrier runs out of samples,
+    /// with [`Header::Length`] Error is returned if the payload is incomplete.
+    pub fn next_byte(&mut self) -> Result<Option<u8>, String> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        match &mut self.remaining {
+            None => {
+                // An even termination flag marks the last byte, bytes without flag are incomplete
+                let byte = read_byte(&mut self.bits).and_then(|byte| Some((byte, self.bits.next()?)));
+                self.finished = !matches!(byte, Some((_, true)));
+                Ok(byte.map(|(byte, _)| byte))
+            }
+            Some(0) => {
+                self.finished = true;
+                Ok(None)
+            }
+            Some(remaining) => {
+                let byte = read_byte(&mut self.bits).ok_or_else(incomplete)?;
+                *remaining -= 1;
+                Ok(Some(byte))
+            }
+        }
+    }
+}
+
+impl Read for StegoReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut len = 0;
+
+        while len < buf.len() {
+            match self.next_byte() {
+                Ok(Some(byte)) => buf[len] = byte,
+                Ok(None) => break,
+                // Bytes read before the error are handed out first, the error is reported by the next call
+                Err(_) if len > 0 => break,
+                Err(err) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, err)),
+            }
+            len += 1;
+        }
+
+        Ok(len)
+    }
+}
+
+impl<C: Carrier + ?Sized> StegoWriter<'_, C> {
+    /// Hides the next byte of the payload. Returns Error if the payload does not fit in the carrier.
+    pub fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        if self.len >= self.capacity {
+            return Err(String::from("The data is too long to be hidden in this carrier. Try using less data or a larger carrier."));
+        }
+        self.len += 1;
+
+        match self.stego.header {
+            // The termination flag of a byte is only known when the next byte arrives
+            Header::Terminated => match self.pending_byte.replace(byte) {
+                Some(previous) => self.write_bits(previous as u32, 8).and_then(|_| self.write_bits(1, 1)),
+                None => Ok(()),
+            },
+            Header::Length => self.write_bits(byte as u32, 8),
+        }
+    }
+
+    /// Completes the payload by writing the end marker or the length in front of the payload.
+    /// Returns the number of samples which were written, or Error if no bytes were written.
+    pub fn finish(mut self) -> Result<usize, String> {
+        if self.len == 0 {
+            return Err(String::from("There is nothing to hide. Try to provide at least one byte."));
+        }
+
+        if let Some(last) = self.pending_byte.take() {
+            self.write_bits(last as u32, 8)?;
+            self.write_bits(0, 1)?;
+        }
+        if self.pending_len > 0 {
+            self.write_bits(0, 4 - self.pending_len)?;
+        }
+
+        self.write_field();
+
+        if self.stego.header == Header::Length {
+            let end = self.position;
+            self.position = 0;
+            self.write_bits(self.len as u32, LENGTH_BITS)?;
+            self.write_field();
+            self.position = end;
+        }
+
+        Ok(self.position.div_ceil(self.stego.depth as usize))
+    }
+
+    /// Writes the lowest `count` bits of the value, least significant bit first, applying the error correction.
+    fn write_bits(&mut self, value: u32, count: usize) -> Result<(), String> {
+        for bit_idx in 0..count {
+            let bit = value.get_bit(bit_idx as u32);
+
+            match self.stego.ecc {
+                Ecc::None => self.write_raw(bit)?,
+                Ecc::Hamming => {
+                    self.pending_bits |= (bit as u8) << self.pending_len;
+                    self.pending_len += 1;
+                    if self.pending_len == 4 {
+                        let code = hamming_encode(self.pending_bits);
+                        (0..7).try_for_each(|pos| self.write_raw(code.get_bit(pos)))?;
+                        self.pending_bits = 0;
+                        self.pending_len = 0;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a hidden bit to the next position. With [`Algorithm::Replacement`] the bits of a sample are
+    /// collected and written together once the sample is complete.
+    fn write_raw(&mut self, bit: bool) -> Result<(), String> {
+        let depth = self.stego.depth as usize;
+
+        match self.stego.algorithm {
+            Algorithm::Replacement => {
+                self.field = self.field.set_bit(self.field_len, bit);
+                self.field_len += 1;
+                self.position += 1;
+                if self.position % depth == 0 {
+                    self.write_field();
+                }
+            }
+            Algorithm::Matching => {
+                let idx = self.stego.sample_idx(self.order.get(self.position / depth));
+                let sample = self.carrier.sample(idx);
+                let value = match sample {
+                    _ if sample.get_lsb() == bit => sample,
+                    0 => 1,
+                    255 => 254,
+                    _ if self.random_bit()? => sample + 1,
+                    _ => sample - 1,
+                };
+                self.carrier.set_sample(idx, value);
+                self.position += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the collected bits to the low bits of their sample, the other bits of the sample are kept.
+    fn write_field(&mut self) {
+        if self.field_len == 0 {
+            return;
+        }
+
+        let depth = self.stego.depth as usize;
+        let start = self.position - self.field_len as usize;
+        let idx = self.stego.sample_idx(self.order.get(start / depth));
+        let value = self.carrier.sample(idx).set_bits((start % depth) as u32, self.field_len, self.field);
+        self.carrier.set_sample(idx, value);
+
+        self.field = 0;
+        self.field_len = 0;
+    }
+
+    fn random_bit(&mut self) -> Result<bool, String> {
+        if self.random_used == self.random.len() * 8 {
+            getrandom::getrandom(&mut self.random).map_err(|err| format!("Failed generating random bits: {err}"))?;
+            self.random_used = 0;
+        }
+
+        let bit = self.random[self.random_used / 8].get_bit((self.random_used % 8) as u32);
+        self.random_used += 1;
+        Ok(bit)
+    }
+}
+
+impl<C: Carrier + ?Sized> Write for StegoWriter<'_, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (len, byte) in buf.iter().enumerate() {
+            if let Err(err) = self.write_byte(*byte) {
+                // Bytes which were hidden are reported first, the error is reported by the next call
+                return match len {
+                    0 => Err(io::Error::new(io::ErrorKind::WriteZero, err)),
+                    _ => Ok(len),
+                };
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn incomplete() -> String {
+    String::from("The carrier does not contain a complete payload.")
+}
+
+/// Returns the next eight bits as byte, least significant bit first.
+fn read_byte(bits: &mut impl Iterator<Item = bool>) -> Option<u8> {
+    (0..8).try_fold(0u8, |byte, bit_idx| Some(byte | (bits.next()? as u8) << bit_idx))
+}
+
+/// Returns the Hamming(7,4) code word of the lowest four bits. Bit `n` of the code word is position `n + 1`,
+/// the parity bits are on the positions 1, 2 and 4.
+fn hamming_encode(data: u8) -> u8 {
+    let d = |idx: u32| data.get_bit(idx) as u8;
+    let parity = [d(0) ^ d(1) ^ d(3), d(0) ^ d(2) ^ d(3), d(1) ^ d(2) ^ d(3)];
+    parity[0] | parity[1] << 1 | d(0) << 2 | parity[2] << 3 | d(1) << 4 | d(2) << 5 | d(3) << 6
+}
+
+/// Returns the four data bits of a Hamming(7,4) code word, correcting a single flipped bit.
+fn hamming_decode(mut code: u8) -> u8 {
+    // The syndrome is the position of the flipped bit, counted from one
+    let syndrome = (0..3).fold(0, |syndrome, parity| {
+        let flipped = (1..=7).filter(|pos| pos & (1 << parity) != 0).fold(0, |sum, pos| sum ^ code.get_bit(pos - 1) as u8);
+        syndrome | flipped << parity
+    });
+    if syndrome != 0 {
+        code ^= 1 << (syndrome - 1);
+    }
+    code.get_bits(2, 1) | code.get_bits(4, 3) << 1
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::carrier::{embed, extract};
+    use crate::color::Color;
+
+    #[test]
+    fn builder_ut() {
+        assert!(Stego::builder().build().is_ok());
+        assert!(Stego::builder().depth(8).channels(&[Channel::Blue, Channel::Red, Channel::Blue]).build().is_ok());
+
+        // Invalid options return Error
+        assert!(Stego::builder().depth(0).build().is_err());
+        assert!(Stego::builder().depth(9).build().is_err());
+        assert!(Stego::builder().algorithm(Algorithm::Matching).depth(2).build().is_err());
+        assert!(Stego::builder().channels(&[]).build().is_err());
+        assert!(Stego::builder().channels(&[Channel::Red, Channel::Alpha]).build().is_err());
+    }
+
+    #[test]
+    fn default_matches_embed_ut() {
+        let bytes = b"Lorem ipsum".to_vec();
+        let mut legacy = vec![0u8; 200];
+        let mut samples = vec![0u8; 200];
+
+        embed(legacy.as_mut_slice(), &bytes).unwrap();
+        assert_eq!(Ok(99), Stego::default().hide(samples.as_mut_slice(), &bytes));
+        assert_eq!(legacy, samples);
+        assert_eq!(Ok(bytes.to_owned()), Stego::default().extract(samples.as_slice()));
+        assert_eq!(extract(&samples[..50]), Stego::default().extract(&samples[..50]).unwrap());
+        assert_eq!(22, Stego::default().capacity(samples.as_slice()));
+    }
+
+    #[test]
+    fn hide_extract_ut() {
+        let bytes = b"Lorem ipsum dolor sit amet".to_vec();
+        let cover: Vec<Color> = (0..400).map(|idx| Color::from_rgb(idx as u8, (idx * 7) as u8, (idx * 13) as u8)).collect();
+
+        let configs = [
+            Stego::builder().depth(3).build().unwrap(),
+            Stego::builder().depth(3).ecc(Ecc::Hamming).header(Header::Length).build().unwrap(),
+            Stego::builder().channels(&[Channel::Green]).header(Header::Length).build().unwrap(),
+            Stego::builder().algorithm(Algorithm::Matching).ecc(Ecc::Hamming).build().unwrap(),
+            Stego::builder()
+                .channels(&[Channel::Red, Channel::Blue])
+                .depth(2)
+                .ecc(Ecc::Hamming)
+                .header(Header::Length)
+                .build()
+                .unwrap(),
+        ];
+
+        for stego in configs {
+            let mut pixels = cover.clone();
+            assert!(stego.hide(pixels.as_mut_slice(), &bytes).is_ok());
+            assert_eq!(Ok(bytes.to_owned()), stego.extract(pixels.as_slice()));
+
+            // The alpha values and the bits above the depth are never touched
+            assert!(pixels.iter().all(|p| p.a == 255));
+            assert!(pixels.iter().zip(&cover).all(|(a, b)| a.r.abs_diff(b.r) < 8 && a.g.abs_diff(b.g) < 8 && a.b.abs_diff(b.b) < 8));
+
+            // Payloads larger than the capacity return Error
+            let too_long = vec![1; stego.capacity(pixels.as_slice()) + 1];
+            assert!(stego.hide(pixels.as_mut_slice(), &too_long).is_err());
+        }
+
+        // Only the selected channels carry bits
+        let mut pixels = cover.clone();
+        let stego = Stego::builder().channels(&[Channel::Green]).build().unwrap();
+        stego.hide(pixels.as_mut_slice(), &bytes).unwrap();
+        assert!(pixels.iter().zip(&cover).all(|(a, b)| a.r == b.r && a.b == b.b));
+
+        // Empty payloads return Error
+        assert!(Stego::default().hide(pixels.as_mut_slice(), &[]).is_err());
+    }
+
+    #[test]
+    fn hamming_ut() {
+        for data in 0..16 {
+            let code = hamming_encode(data);
+            assert_eq!(data, hamming_decode(code));
+
+            // Every single flipped bit is corrected
+            for pos in 0..7 {
+                assert_eq!(data, hamming_decode(code ^ 1 << pos));
+            }
+        }
+    }
+
+    #[test]
+    fn ecc_ut() {
+        let bytes = b"Lorem ipsum".to_vec();
+        let stego = Stego::builder().ecc(Ecc::Hamming).header(Header::Length).build().unwrap();
+        let mut samples = vec![0u8; 400];
+        stego.hide(samples.as_mut_slice(), &bytes).unwrap();
+
+        // One flipped bit in every code word is corrected
+        for word in 0..(4 + bytes.len()) * 2 {
+            samples[word * 7 + word % 7] ^= 1;
+        }
+        assert_eq!(Ok(bytes), stego.extract(samples.as_slice()));
+
+        // A length exceeding the carrier returns Error
+        let stego = Stego::builder().header(Header::Length).build().unwrap();
+        let mut samples = vec![1u8; 100];
+        assert!(stego.extract(samples.as_slice()).is_err());
+        samples.fill(0);
+        assert_eq!(Ok(vec![]), stego.extract(samples.as_slice()));
+    }
+
+    /// Carrier which records the highest position that was read.
+    struct Tracked {
+        samples: Vec<u8>,
+        last: std::cell::Cell<usize>,
+    }
+
+    impl Carrier for Tracked {
+        fn sample_count(&self) -> usize {
+            self.samples.len()
+        }
+
+        fn sample(&self, idx: usize) -> u8 {
+            self.last.set(self.last.get().max(idx));
+            self.samples[idx]
+        }
+
+        fn set_sample(&mut self, idx: usize, value: u8) {
+            self.samples[idx] = value;
+        }
+    }
+
+    #[test]
+    fn reader_ut() {
+        let bytes = b"Lorem ipsum".to_vec();
+
+        for stego in [Stego::default(), Stego::builder().depth(2).header(Header::Length).build().unwrap()] {
+            let mut carrier = Tracked {
+                samples: vec![0u8; 100_000],
+                last: Default::default(),
+            };
+            let written = stego.hide(&mut carrier, &bytes).unwrap();
+
+            // Only the samples holding the payload are read
+            let mut payload = vec![];
+            stego.reader(&carrier).unwrap().read_to_end(&mut payload).unwrap();
+            assert_eq!(bytes, payload);
+            assert_eq!(written - 1, carrier.last.get());
+
+            // Bytes can be read in small chunks
+            let mut reader = stego.reader(&carrier).unwrap();
+            let mut chunk = [0u8; 4];
+            assert_eq!(4, reader.read(&mut chunk).unwrap());
+            assert_eq!(b"Lore", &chunk);
+            assert_eq!(Ok(Some(b'm')), reader.next_byte());
+        }
+
+        // Truncated carriers return the complete bytes, or Error if the length does not fit
+        let mut samples = vec![0u8; 200];
+        Stego::default().hide(samples.as_mut_slice(), &bytes).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(4, Stego::default().reader(&samples[..40]).unwrap().read(&mut buf).unwrap());
+
+        let stego = Stego::builder().header(Header::Length).build().unwrap();
+        stego.hide(samples.as_mut_slice(), &bytes).unwrap();
+        assert!(stego.reader(&samples[..70]).is_err());
+
+        // Keyed orders are read lazily as well
+        #[cfg(feature = "crypto")]
+        for stego in [
+            Stego::builder().key("key").build().unwrap(),
+            Stego::builder().key("key").depth(2).header(Header::Length).build().unwrap(),
+        ] {
+            let mut samples = vec![0u8; 100_000];
+            stego.hide(samples.as_mut_slice(), &bytes).unwrap();
+            assert!(samples[50_000..].contains(&1));
+
+            let mut reader = stego.reader(samples.as_slice()).unwrap();
+            let mut chunk = [0u8; 4];
+            assert_eq!(4, reader.read(&mut chunk).unwrap());
+            assert_eq!(b"Lore", &chunk);
+
+            let mut payload = chunk.to_vec();
+            reader.read_to_end(&mut payload).unwrap();
+            assert_eq!(bytes, payload);
+        }
+    }
+
+    #[test]
+    fn writer_ut() {
+        let bytes: Vec<u8> = (0..600).map(|n| (n % 251) as u8).collect();
+        let cover: Vec<Color> = (0..4000).map(|idx| Color::from_rgb(idx as u8, (idx * 7) as u8, (idx * 13) as u8)).collect();
+
+        let configs = [
+            Stego::default(),
+            Stego::builder().depth(3).ecc(Ecc::Hamming).build().unwrap(),
+            Stego::builder().channels(&[Channel::Red, Channel::Blue]).depth(2).header(Header::Length).build().unwrap(),
+            Stego::builder().ecc(Ecc::Hamming).header(Header::Length).build().unwrap(),
+        ];
+
+        for stego in configs {
+            // Writing in chunks produces the same carrier as hiding the payload as a whole
+            let mut hidden = cover.clone();
+            let samples = stego.hide(hidden.as_mut_slice(), &bytes).unwrap();
+
+            let mut pixels = cover.clone();
+            let mut writer = stego.writer(pixels.as_mut_slice()).unwrap();
+            for chunk in bytes.chunks(7) {
+                writer.write_all(chunk).unwrap();
+            }
+            assert_eq!(Ok(samples), writer.finish());
+            assert_eq!(hidden, pixels);
+            assert_eq!(Ok(bytes.to_owned()), stego.extract(pixels.as_slice()));
+        }
+
+        // Keyed orders are written lazily as well
+        #[cfg(feature = "crypto")]
+        for stego in [
+            Stego::builder().key("key").build().unwrap(),
+            Stego::builder().key("key").ecc(Ecc::Hamming).header(Header::Length).build().unwrap(),
+        ] {
+            let mut hidden = cover.clone();
+            let samples = stego.hide(hidden.as_mut_slice(), &bytes).unwrap();
+
+            let mut pixels = cover.clone();
+            let mut writer = stego.writer(pixels.as_mut_slice()).unwrap();
+            for chunk in bytes.chunks(7) {
+                writer.write_all(chunk).unwrap();
+            }
+            assert_eq!(Ok(samples), writer.finish());
+            assert_eq!(hidden, pixels);
+            assert_eq!(Ok(bytes.to_owned()), stego.extract(pixels.as_slice()));
+        }
+
+        // Matching changes the values by one
+        let stego = Stego::builder().algorithm(Algorithm::Matching).build().unwrap();
+        let mut pixels = cover.clone();
+        let mut writer = stego.writer(pixels.as_mut_slice()).unwrap();
+        writer.write_all(&bytes).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(Ok(bytes.to_owned()), stego.extract(pixels.as_slice()));
+        assert!(pixels.iter().zip(&cover).all(|(a, b)| a.r.abs_diff(b.r) <= 1 && a.g.abs_diff(b.g) <= 1 && a.b.abs_diff(b.b) <= 1));
+
+        // Bytes beyond the capacity are rejected, the accepted bytes are hidden
+        let stego = Stego::default();
+        let mut samples = vec![0u8; 100];
+        let mut writer = stego.writer(samples.as_mut_slice()).unwrap();
+        assert_eq!(11, writer.write(&[1; 20]).unwrap());
+        assert_eq!(io::ErrorKind::WriteZero, writer.write(&[1]).unwrap_err().kind());
+        assert_eq!(Ok(99), writer.finish());
+        assert_eq!(Ok(vec![1; 11]), stego.extract(samples.as_slice()));
+
+        // Writers without bytes return Error
+        assert!(stego.writer(samples.as_mut_slice()).unwrap().finish().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn key_encryption_ut() {
+        let bytes = b"Lorem ipsum".to_vec();
+        let stego = Stego::builder().key("key").encryption("password").header(Header::Length).build().unwrap();
+        let mut samples = vec![0u8; 2000];
+        stego.hide(samples.as_mut_slice(), &bytes).unwrap();
+        assert_eq!(Ok(bytes.to_owned()), stego.extract(samples.as_slice()));
+
+        // The bits are spread over the whole carrier
+        assert!(samples[1000..].contains(&1));
+
+        // Wrong keys or passwords return Error
+        assert!(Stego::builder()
+            .key("other")
+            .encryption("password")
+            .header(Header::Length)
+            .build()
+            .unwrap()
+            .extract(samples.as_slice())
+            .is_err());
+        assert!(Stego::builder()
+            .key("key")
+            .encryption("wrong")
+            .header(Header::Length)
+            .build()
+            .unwrap()
+            .extract(samples.as_slice())
+            .is_err());
+        assert!(Stego::builder().key("").build().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn exact_capacity_ut() {
+        let mut samples = vec![0u8; 2000];
+
+        // Encrypted payloads of exactly the capacity fit, one more byte does not
+        let stego = Stego::builder().encryption("password").build().unwrap();
+        let bytes = vec![42u8; stego.capacity(samples.as_slice())];
+        assert!(stego.hide(samples.as_mut_slice(), &bytes).is_ok());
+        assert_eq!(Ok(bytes.to_owned()), stego.extract(samples.as_slice()));
+        assert!(stego.hide(samples.as_mut_slice(), &[bytes.as_slice(), &[42]].concat()).is_err());
+
+        #[cfg(feature = "compression")]
+        {
+            let stego = Stego::builder().encryption("password").compression(6).header(Header::Length).build().unwrap();
+            let bytes = vec![42u8; stego.capacity(samples.as_slice())];
+            assert!(stego.hide(samples.as_mut_slice(), &bytes).is_ok());
+            assert_eq!(Ok(bytes), stego.extract(samples.as_slice()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compression_ut() {
+        let bytes = vec![42u8; 1000];
+        let stego = Stego::builder().compression(6).build().unwrap();
+        let mut samples = vec![0u8; 1000];
+
+        // Compressed payloads fit in carriers too small for the raw payload
+        assert!(Stego::default().hide(samples.as_mut_slice(), &bytes).is_err());
+        assert!(stego.hide(samples.as_mut_slice(), &bytes).is_ok());
+        assert_eq!(Ok(bytes), stego.extract(samples.as_slice()));
+        assert!(stego.reader(samples.as_slice()).is_err());
+        assert!(stego.writer(samples.as_mut_slice()).is_err());
+        assert!(Stego::builder().compression(11).build().is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::carrier::{capacity, embed, extract, Carrier};
+
+/// Marks a payload as part of a striped payload, followed by the format version.
+const MAGIC: &[u8; 4] = b"HST\x01";
+
+/// Number of bytes in front of every part: magic bytes, the random id shared by all parts,
+/// the sequence number of the part and the number of parts, both as little endian `u16`.
+pub const PART_HEADER_LEN: usize = MAGIC.len() + 4 + 2 + 2;
+
+/// Hides a payload which is too large for a single carrier across several carriers.
+///
+/// The payload is split into consecutive parts which fill the covers in the given order, every part is preceded
+/// by a header with the id of the payload, its sequence number and the number of parts. Covers which are not
+/// needed are left untouched. Returns the number of covers which carry a part.
+///
+/// # Arguments
+///
+/// * `covers` - Carriers the parts will be hidden in, e.g. images or mutable pixel vectors.
+/// * `payload` - Bytes which will be hidden.
+///
+pub fn hide_across(covers: &mut [impl Carrier], payload: &[u8]) -> Result<usize, String> {
+    if payload.is_empty() {
+        return Err(String::from("There is nothing to hide. Try to provide at least one byte."));
+    }
+
+    // Number of payload bytes every cover can carry behind the header
+    let sizes: Vec<usize> = covers.iter().map(|cover| capacity(cover.sample_count()).saturating_sub(PART_HEADER_LEN)).collect();
+
+    let mut count = 0;
+    let mut remaining = payload.len();
+    for size in sizes.iter().filter(|size| **size > 0) {
+        if remaining == 0 {
+            break;
+        }
+        remaining = remaining.saturating_sub(*size);
+        count += 1;
+    }
+
+    if remaining > 0 {
+        return Err(String::from("The payload is too long to be hidden in these covers. Try using less data or more covers."));
+    }
+    if count > u16::MAX as usize {
+        return Err(String::from("The payload can be split into at most 65535 parts. Try using larger covers."));
+    }
+
+    let mut id = [0u8; 4];
+    getrandom::getrandom(&mut id).map_err(|err| format!("Failed generating random bytes: {err}"))?;
+
+    let mut rest = payload;
+    let mut sequence = 0;
+    for (cover, size) in covers.iter_mut().zip(sizes) {
+        if rest.is_empty() {
+            break;
+        }
+        if size == 0 {
+            continue;
+        }
+
+        let (part, tail) = rest.split_at(size.min(rest.len()));
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(id);
+        bytes.extend((sequence as u16).to_le_bytes());
+        bytes.extend((count as u16).to_le_bytes());
+        bytes.extend(part);
+        embed(cover, &bytes)?;
+
+        rest = tail;
+        sequence += 1;
+    }
+
+    Ok(count)
+}
+
+/// Returns the payload reassembled from the parts hidden in the carriers by [`hide_across`].
+/// The carriers may be provided in any order, carriers without a part are skipped. If the carriers hold parts
+/// of several payloads, the first complete payload is returned. Returns Error naming the missing parts if no payload is complete.
+///
+/// # Arguments
+///
+/// * `carriers` - Carriers which carry the parts of the payload.
+///
+pub fn find_across<C: Carrier + ?Sized>(carriers: &[&C]) -> Result<Vec<u8>, String> {
+    // One reassembler per payload, in the order the payloads are first seen
+    let mut reassemblers: Vec<Reassembler> = vec![];
+    for carrier in carriers {
+        let bytes = extract(*carrier);
+        if reassemblers.iter_mut().any(|reassembler| reassembler.add_bytes(&bytes).is_ok()) {
+            continue;
+        }
+
+        let mut reassembler = Reassembler::new();
+        if reassembler.add_bytes(&bytes).is_ok() {
+            reassemblers.push(reassembler);
+        }
+    }
+
+    match reassemblers.iter().position(|reassembler| reassembler.is_complete()) {
+        Some(idx) => reassemblers.swap_remove(idx).finish(),
+        None => reassemblers.into_iter().next().unwrap_or_default().finish(),
+    }
+}
+
+/// Collects the parts of a payload hidden by [`hide_across`] in any order.
+#[derive(Default)]
+pub struct Reassembler {
+    id: Option<[u8; 4]>,
+    parts: Vec<Option<Vec<u8>>>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Reassembler::default()
+    }
+
+    /// Reads the part hidden in the carrier. Returns Error if the carrier does not carry a part of the same payload
+    /// as the previously added carriers, the reassembler is left unchanged in that case.
+    ///
+    /// # Arguments
+    ///
+    /// * `carrier` - Carrier which carries a part of the payload.
+    ///
+    pub fn add<C: Carrier + ?Sized>(&mut self, carrier: &C) -> Result<(), String> {
+        self.add_bytes(&extract(carrier))
+    }
+
+    fn add_bytes(&mut self, bytes: &[u8]) -> Result<(), String> {
+        if bytes.len() < PART_HEADER_LEN || !bytes.starts_with(MAGIC) {
+            return Err(String::from("The carrier does not contain a part of a striped payload."));
+        }
+
+        let id: [u8; 4] = bytes[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap();
+        let sequence = u16::from_le_bytes(bytes[MAGIC.len() + 4..MAGIC.len() + 6].try_into().unwrap()) as usize;
+        let count = u16::from_le_bytes(bytes[MAGIC.len() + 6..PART_HEADER_LEN].try_into().unwrap()) as usize;
+
+        if sequence >= count {
+            return Err(String::from("The carrier contains an invalid part."));
+        }
+
+        match self.id {
+            None => {
+                self.id = Some(id);
+                self.parts = vec![None; count];
+            }
+            Some(expected) if expected != id || self.parts.len() != count => {
+                return Err(String::from("The carrier contains a part of another payload."));
+            }
+            _ => (),
+        }
+
+        self.parts[sequence] = Some(bytes[PART_HEADER_LEN..].to_vec());
+
+        Ok(())
+    }
+
+    /// Returns the sequence numbers of the parts which have not been added yet, starting at zero.
+    pub fn missing(&self) -> Vec<usize> {
+        self.parts.iter().enumerate().filter(|(_, part)| part.is_none()).map(|(sequence, _)| sequence).collect()
+    }
+
+    /// Returns whether all parts have been added.
+    pub fn is_complete(&self) -> bool {
+        self.id.is_some() && self.missing().is_empty()
+    }
+
+    /// Returns the reassembled payload. Returns Error naming the missing parts if the payload is incomplete.
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        if self.id.is_none() {
+            return Err(String::from("No parts of a striped payload have been found."));
+        }
+
+        let missing = self.missing();
+        if !missing.is_empty() {
+            return Err(format!("The parts {missing:?} of {} parts are missing.", self.parts.len()));
+        }
+
+        Ok(self.parts.into_iter().flatten().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn hide_find_across_ut() {
+        let payload: Vec<u8> = (0..800).map(|n| (n % 251) as u8).collect();
+        let mut covers = vec![vec![Color::new(); 1000]; 4];
+
+        // The payload fills the first three covers, the last cover is untouched
+        let mut refs: Vec<&mut [Color]> = covers.iter_mut().map(|cover| cover.as_mut_slice()).collect();
+        assert_eq!(Ok(3), hide_across(&mut refs, &payload));
+        assert!(covers[3].iter().all(|p| *p == Color::new()));
+
+        // Covers are reassembled in any order
+        let found = find_across(&[covers[2].as_slice(), covers[0].as_slice(), covers[1].as_slice()]);
+        assert_eq!(Ok(payload.to_owned()), found);
+
+        // Missing parts are reported
+        let mut reassembler = Reassembler::new();
+        assert!(reassembler.add(covers[2].as_slice()).is_ok());
+        assert!(reassembler.add(covers[0].as_slice()).is_ok());
+        assert!(!reassembler.is_complete());
+        assert_eq!(vec![1], reassembler.missing());
+        assert!(reassembler.finish().is_err());
+
+        // Parts with an invalid sequence number or count are rejected without changing the reassembler
+        let mut invalid = vec![Color::new(); 1000];
+        for (sequence, count) in [(2u16, 2u16), (0, 0)] {
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend([0; 4]);
+            bytes.extend(sequence.to_le_bytes());
+            bytes.extend(count.to_le_bytes());
+            bytes.extend([1, 2, 3]);
+            embed(invalid.as_mut_slice(), &bytes).unwrap();
+
+            let mut reassembler = Reassembler::new();
+            assert!(reassembler.add(invalid.as_slice()).is_err());
+            assert!(reassembler.add(covers[0].as_slice()).is_ok());
+            assert_eq!(vec![1, 2], reassembler.missing());
+        }
+
+        // Covers without parts and parts of another payload are skipped
+        let mut others = vec![vec![Color::new(); 200]; 2];
+        let mut refs: Vec<&mut [Color]> = others.iter_mut().map(|cover| cover.as_mut_slice()).collect();
+        assert_eq!(Ok(2), hide_across(&mut refs, &[7; 100]));
+        let found = find_across(&[
+            others[0].as_slice(),
+            covers[3].as_slice(),
+            covers[2].as_slice(),
+            invalid.as_slice(),
+            covers[0].as_slice(),
+            covers[1].as_slice(),
+        ]);
+        assert_eq!(Ok(payload.to_owned()), found);
+        assert_eq!(Ok(vec![7; 100]), find_across(&[covers[0].as_slice(), others[1].as_slice(), others[0].as_slice()]));
+
+        // Covers without any complete payload return Error
+        assert!(find_across(&[covers[3].as_slice()]).is_err());
+        assert!(find_across(&[covers[0].as_slice(), others[0].as_slice()]).is_err());
+        assert!(find_across::<[Color]>(&[]).is_err());
+
+        // Payloads which do not fit and empty payloads return Error
+        let (mut first, mut second) = (vec![0u8; 2000], vec![0u8; 2000]);
+        assert!(hide_across(&mut [first.as_mut_slice(), second.as_mut_slice()], &payload).is_err());
+        assert!(first.iter().chain(&second).all(|s| *s == 0));
+        assert!(hide_across(&mut [first.as_mut_slice()], &[]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn hide_find_across_img_ut() {
+        let cover = image::open("test_images/peppers.png").unwrap();
+        let mut covers = vec![cover.clone(), cover.clone()];
+        let payload = vec![42u8; 15000];
+
+        assert_eq!(Ok(2), hide_across(&mut covers, &payload));
+        assert_eq!(Ok(payload), find_across(&[&covers[1], &covers[0]]));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::bit_ops::{BitOrder, BitReader, BitWriter};
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::SecretString;
+
+/// Zero width space, encodes a `0` bit.
+const ZERO: char = '\u{200B}';
+
+/// Zero width non-joiner, encodes a `1` bit.
+const ONE: char = '\u{200C}';
+
+/// Zero width joiner, terminates the hidden bits.
+const END: char = '\u{200D}';
+
+/// Hides a secret in a cover text using invisible zero width characters.
+///
+/// Every bit of the secret is written as zero width space (`0`) or zero width non-joiner (`1`),
+/// the sequence is terminated by a zero width joiner. The characters are inserted in front of the first
+/// whitespace of the cover, so the visible text stays the same.
+///
+/// # Arguments
+///
+/// * `cover` - Innocuous text which will carry the secret.
+/// * `secret` - Secret text which will be hidden in the cover text.
+///
+pub fn hide_in_text(cover: &str, secret: &str, password: Option<SecretString>) -> Result<String, String> {
+    if cover.is_empty() {
+        return Err(String::from("You have entered an empty cover text. Try to use at least one word as cover text."));
+    }
+
+    if find_hidden_bytes(cover).is_some() {
+        return Err(String::from("The cover text already contains a hidden secret."));
+    }
+
+    let secret_bytes = secret_to_bytes(secret, password)?;
+
+    let mut hidden: String = BitReader::new(&secret_bytes, BitOrder::LsbFirst).map(|bit| if bit { ONE } else { ZERO }).collect();
+    hidden.push(END);
+
+    let position = cover.find(char::is_whitespace).unwrap_or(cover.len());
+    let mut result = String::with_capacity(cover.len() + hidden.len());
+    result.push_str(&cover[..position]);
+    result.push_str(&hidden);
+    result.push_str(&cover[position..]);
+
+    Ok(result)
+}
+
+/// Returns a secret retrieved from the zero width characters of a text if it exists.
+///
+/// # Arguments
+///
+/// * `text` - Text which will be searched for a hidden secret.
+///
+pub fn find_in_text(text: &str, password: Option<SecretString>) -> Option<SecretString> {
+    bytes_to_secret(find_hidden_bytes(text)?, password)
+}
+
+/// Returns the text with all zero width characters used for hiding secrets removed.
+///
+/// # Arguments
+///
+/// * `text` - Text which may contain a hidden secret.
+///
+pub fn strip_hidden(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut hidden = false;
+
+    for c in text.chars() {
+        match c {
+            ZERO | ONE => hidden = true,
+            END if hidden => hidden = false,
+            _ => {
+                hidden = false;
+                result.push(c);
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the bytes of the first run of hidden bit characters which is terminated by the end character.
+/// Zero width joiners within emoji sequences are not preceded by hidden bits and are ignored.
+fn find_hidden_bytes(text: &str) -> Option<Vec<u8>> {
+    let mut bits = BitWriter::new(BitOrder::LsbFirst);
+
+    for c in text.chars() {
+        match c {
+            ZERO => bits.write_bit(false),
+            ONE => bits.write_bit(true),
+            END if !bits.is_empty() && bits.len() % 8 == 0 => return Some(bits.into_bytes()),
+            _ => bits.clear(),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn hide_find_in_text_ut() {
+        let cover = "Lorem ipsum dolor sit amet.";
+        let secret = String::from("0123456789");
+
+        // Text with no secret returns None
+        assert_eq!(None, find_in_text(cover, None));
+
+        // Successfully hide and find a secret
+        let result = hide_in_text(cover, &secret, None);
+        assert!(result.is_ok());
+        let text = result.unwrap();
+        assert_ne!(cover, text);
+        assert!(text.starts_with("Lorem\u{200B}"));
+        assert_eq!(cover, strip_hidden(&text));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_in_text(&text, None));
+
+        // Hiding a second secret in the same text returns Error
+        assert!(hide_in_text(&text, &secret, None).is_err());
+
+        // Providing password will encrypt decrypt
+        let password = SecretString::from("Ipsum Lorem");
+        let text = hide_in_text(cover, &secret, Some(password.to_owned())).unwrap();
+        assert_ne!(Some(SecretString::from(secret.to_owned())), find_in_text(&text, None));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_in_text(&text, Some(password)));
+
+        // Cover texts without whitespace carry the secret at the end
+        let text = hide_in_text("Lorem", &secret, None).unwrap();
+        assert!(text.ends_with(END));
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_in_text(&text, None));
+
+        // Emoji sequences joined by zero width joiners are left alone
+        let cover = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} family";
+        let text = hide_in_text(cover, &secret, None).unwrap();
+        assert_eq!(Some(SecretString::from(secret.to_owned())), find_in_text(&text, None));
+        assert_eq!(cover, strip_hidden(&text));
+
+        // Providing an empty secret or an empty cover returns Error
+        assert!(hide_in_text(cover, "", None).is_err());
+        assert!(hide_in_text("", &secret, None).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use zeroize::Zeroizing;
+
+use crate::carrier::{Embedder, Extractor, SAMPLES_PER_BYTE};
+use crate::hips::{bytes_to_secret, secret_to_bytes};
+use crate::secret::SecretString;
+
+/// Number of bytes in front of the secret: the number of frames spanned by the payload and the
+/// number of Y samples used in the last frame, both as little endian `u32`.
+pub const PAYLOAD_HEADER_LEN: usize = 8;
+
+/// Largest frame in bytes which is read into memory, enough for an 8K frame with alpha plane.
+pub const MAX_FRAME_LEN: usize = 1 << 28;
+
+/// Stream header of an uncompressed YUV4MPEG2 video.
+struct Y4mHeader {
+    /// The raw header line including the line break, written back unchanged.
+    line: Vec<u8>,
+    /// Number of samples in the luma (Y) plane of every frame.
+    luma_len: usize,
+    /// Number of bytes of every frame including the chroma planes.
+    frame_len: usize,
+}
+
+impl Y4mHeader {
+    /// Tries to read and parse the stream header of a Y4M video.
+    fn read<R: BufRead>(reader: &mut R) -> Result<Y4mHeader, String> {
+        let mut line = vec![];
+        reader.read_until(b'\n', &mut line).map_err(|err| format!("Failed reading Y4M video: {err}"))?;
+
+        let text = String::from_utf8_lossy(&line);
+        let mut params = text.trim_end().split(' ');
+        if params.next() != Some("YUV4MPEG2") {
+            return Err(String::from("The video is not a YUV4MPEG2 stream."));
+        }
+
+        let (mut width, mut height, mut colorspace) = (0, 0, "420jpeg");
+        for param in params {
+            if let Some(value) = param.strip_prefix('W') {
+                width = value.parse().map_err(|_| String::from("The Y4M header contains an invalid width."))?;
+            } else if let Some(value) = param.strip_prefix('H') {
+                height = value.parse().map_err(|_| String::from("The Y4M header contains an invalid height."))?;
+            } else if let Some(value) = param.strip_prefix('C') {
+                colorspace = value;
+            }
+        }
+
+        let invalid = || String::from("The Y4M header contains an invalid frame size.");
+        let luma_len: usize = usize::checked_mul(width, height).ok_or_else(invalid)?;
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        let chroma_len = match colorspace {
+            "420" | "420jpeg" | "420paldv" | "420mpeg2" => chroma_width.checked_mul(chroma_height).and_then(|len| len.checked_mul(2)),
+            "422" => chroma_width.checked_mul(height).and_then(|len| len.checked_mul(2)),
+            "444" => luma_len.checked_mul(2),
+            "444alpha" => luma_len.checked_mul(3),
+            "mono" => Some(0),
+            _ => return Err(format!("The Y4M colorspace '{colorspace}' is not supported. Only 8 bit colorspaces are supported.")),
+        };
+        let frame_len = chroma_len.and_then(|len| len.checked_add(luma_len)).filter(|len| *len <= MAX_FRAME_LEN).ok_or_else(invalid)?;
+
+        if luma_len == 0 {
+            return Err(String::from("The Y4M header does not contain a valid frame size."));
+        }
+
+        Ok(Y4mHeader { line, luma_len, frame_len })
+    }
+
+    /// Tries to read the next frame into the buffer. Returns the raw frame header line or `None` at the end of the stream.
+    fn read_frame<R: BufRead>(&self, reader: &mut R, buffer: &mut Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+        let mut line = vec![];
+        reader.read_until(b'\n', &mut line).map_err(|err| format!("Failed reading Y4M video: {err}"))?;
+
+        if line.is_empty() {
+            return Ok(None);
+        }
+        if !line.starts_with(b"FRAME") {
+            return Err(String::from("The Y4M video contains an invalid frame header."));
+        }
+
+        buffer.resize(self.frame_len, 0);
+        reader.read_exact(buffer).map_err(|_| String::from("The Y4M video is truncated."))?;
+
+        Ok(Some(line))
+    }
+}
+
+/// Tries to load a Y4M video, hide the given secret in it and write the result to another file.
+///
+/// # Arguments
+///
+/// * `video_path` - Path to the source video file.
+/// * `output_path` - Path of the target video file.
+/// * `secret` - Secret text which will be hidden in the video.
+///
+pub fn hide_secret_y4m_file(video_path: &str, output_path: &str, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    let input = File::open(video_path).map_err(|_| format!("Failed loading input video '{video_path}'"))?;
+    let output = File::create(output_path).map_err(|_| format!("Failed creating output video '{output_path}'"))?;
+    hide_secret_y4m(BufReader::new(input), BufWriter::new(output), secret, password)
+}
+
+/// Tries to load a Y4M video and searches it for hidden secrets.
+///
+/// # Arguments
+///
+/// * `video_path` - Path to the video which will be searched for hidden secrets.
+///
+pub fn find_secret_y4m_file(video_path: &str, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let input = File::open(video_path).map_err(|_| format!("Failed loading input video '{video_path}'"))?;
+    find_secret_y4m(BufReader::new(input), password)
+}
+
+/// Hides a secret string in the luma (Y) planes of an uncompressed Y4M video.
+///
+/// The video is streamed frame by frame, only a single frame is kept in memory. The secret is preceded by
+/// a header recording the number of frames it spans and the number of samples used in the last frame.
+/// If the video ends before the secret is completely hidden an error is returned and the output is incomplete.
+///
+/// # Arguments
+///
+/// * `reader` - Source of the Y4M video.
+/// * `writer` - Target of the Y4M video with the hidden secret.
+/// * `secret` - Secret string which will be hidden in the video.
+///
+pub fn hide_secret_y4m<R: BufRead, W: Write>(mut reader: R, mut writer: W, secret: &str, password: Option<SecretString>) -> Result<(), String> {
+    let header = Y4mHeader::read(&mut reader)?;
+    let secret_bytes = secret_to_bytes(secret, password)?;
+
+    let samples = (PAYLOAD_HEADER_LEN + secret_bytes.len()) * SAMPLES_PER_BYTE;
+    let frame_count = samples.div_ceil(header.luma_len);
+    let last_frame_samples = samples - (frame_count - 1) * header.luma_len;
+
+    let mut payload = Zeroizing::new(Vec::with_capacity(PAYLOAD_HEADER_LEN + secret_bytes.len()));
+    payload.extend((frame_count as u32).to_le_bytes());
+    payload.extend((last_frame_samples as u32).to_le_bytes());
+    payload.extend_from_slice(&secret_bytes);
+
+    let write_error = |err: std::io::Error| format!("Failed writing Y4M video: {err}");
+    writer.write_all(&header.line).map_err(write_error)?;
+
+    let mut embedder = Embedder::new(&payload);
+    let mut buffer = vec![];
+    while let Some(frame_line) = header.read_frame(&mut reader, &mut buffer)? {
+        embedder.embed_into(&mut buffer[..header.luma_len]);
+        writer.write_all(&frame_line).and_then(|_| writer.write_all(&buffer)).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
+
+    if !embedder.is_finished() {
+        return Err(String::from("The message is too long to be hidden in this video. Try using a shorter message or a longer video."));
+    }
+
+    Ok(())
+}
+
+/// Returns a secret string retrieved from the luma (Y) planes of an uncompressed Y4M video if it exists.
+/// Reading stops after the last frame carrying the secret.
+///
+/// # Arguments
+///
+/// * `reader` - Source of the Y4M video.
+///
+pub fn find_secret_y4m<R: BufRead>(mut reader: R, password: Option<SecretString>) -> Result<Option<SecretString>, String> {
+    let header = Y4mHeader::read(&mut reader)?;
+
+    let mut extractor = Extractor::new();
+    let mut buffer = vec![];
+    let (mut frame_count, mut last_frame_samples) = (0, 0);
+    while !extractor.is_finished() && header.read_frame(&mut reader, &mut buffer)?.is_some() {
+        last_frame_samples = extractor.extract_from(&buffer[..header.luma_len]);
+        frame_count += 1;
+    }
+
+    let bytes = extractor.into_bytes();
+    if bytes.len() < PAYLOAD_HEADER_LEN {
+        return Ok(None);
+    }
+
+    // The secret has to end exactly where the header says it does
+    let expected_frames = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+    let expected_samples = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if expected_frames != frame_count || expected_samples as usize != last_frame_samples {
+        return Ok(None);
+    }
+
+    Ok(bytes_to_secret(bytes[PAYLOAD_HEADER_LEN..].to_vec(), password))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Returns a Y4M video with the given number of 4:2:0 frames.
+    fn test_video(width: usize, height: usize, frames: usize) -> Vec<u8> {
+        let mut video = format!("YUV4MPEG2 W{width} H{height} F25:1 Ip A1:1 C420jpeg\n").into_bytes();
+        for n in 0..frames {
+            video.extend(b"FRAME\n");
+            video.extend((0..width * height).map(|i| (i * 7 + n) as u8));
+            video.extend(vec![128u8; 2 * width.div_ceil(2) * height.div_ceil(2)]);
+        }
+        video
+    }
+
+    #[test]
+    fn hide_find_secret_y4m_ut() {
+        let video = test_video(16, 16, 8);
+        let secret: String = "Lorem ipsum dolor sit amet. ".repeat(3);
+        let password = SecretString::from("password");
+
+        // Video with no secret returns None
+        assert_eq!(Ok(None), find_secret_y4m(video.as_slice(), None));
+
+        // Successfully hide a secret spanning several frames
+        let mut output = vec![];
+        assert!(hide_secret_y4m(video.as_slice(), &mut output, &secret, Some(password.to_owned())).is_ok());
+        assert_eq!(video.len(), output.len());
+        assert_eq!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_y4m(output.as_slice(), Some(password.to_owned())));
+        assert_ne!(Ok(Some(SecretString::from(secret.to_owned()))), find_secret_y4m(output.as_slice(), None));
+
+        // Only the luma planes are touched
+        let frame_len = 6 + 256 + 2 * 8 * 8;
+        let header_len = video.len() - 8 * frame_len;
+        for (frame, output_frame) in video[header_len..].chunks(frame_len).zip(output[header_len..].chunks(frame_len)) {
+            assert_eq!(frame[6 + 256..], output_frame[6 + 256..]);
+            assert!(frame.iter().zip(output_frame).all(|(a, b)| a.abs_diff(*b) <= 1));
+        }
+
+        // A truncated video loses the secret
+        let truncated = &output[..header_len + 2 * frame_len];
+        assert_eq!(Ok(None), find_secret_y4m(truncated, Some(password)));
+
+        // Return Error when secret is too long for given video
+        assert!(hide_secret_y4m(video.as_slice(), vec![], &secret.repeat(3), None).is_err());
+
+        // Streams which are no Y4M videos return Error
+        assert!(find_secret_y4m(&b"RIFF"[..], None).is_err());
+        assert!(find_secret_y4m(&b"YUV4MPEG2 W10 H8 C420p10\n"[..], None).is_err());
+
+        // Oversized frames return Error instead of allocating the frame buffer
+        let oversized = Err(String::from("The Y4M header contains an invalid frame size."));
+        assert_eq!(oversized, find_secret_y4m(&b"YUV4MPEG2 W100000 H100000 C420jpeg\nFRAME\n"[..], None));
+        assert_eq!(oversized, find_secret_y4m(&b"YUV4MPEG2 W4294967296 H4294967296 C444\nFRAME\n"[..], None));
+        assert_eq!(oversized, find_secret_y4m(&b"YUV4MPEG2 W3037000499 H3037000499 C444alpha\nFRAME\n"[..], None));
+    }
+
+    #[test]
+    fn hide_find_secret_y4m_file_ut() {
+        // Non existent videos return Error
+        assert!(hide_secret_y4m_file("test_images/non_existent_video.y4m", "out.y4m", "Lorem", None).is_err());
+        assert!(find_secret_y4m_file("test_images/non_existent_video.y4m", None).is_err());
+
+        // Successfully hide and find a secret in a video file
+        let secret = String::from("Lorem ipsum");
+        let input = std::env::temp_dir().join("hips_video_ut.y4m");
+        let output = std::env::temp_dir().join("hips_video_ut_secret.y4m");
+        std::fs::write(&input, test_video(16, 16, 2)).unwrap();
+
+        assert!(hide_secret_y4m_file(input.to_str().unwrap(), output.to_str().unwrap(), &secret, None).is_ok());
+        assert_eq!(Ok(Some(SecretString::from(secret))), find_secret_y4m_file(output.to_str().unwrap(), None));
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}
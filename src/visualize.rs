@@ -0,0 +1,101 @@
+use image::{DynamicImage, GenericImageView, GrayImage, Luma, Rgb, RgbImage};
+
+use crate::bit_ops::BitOps;
+use crate::color::Channel;
+
+/// Heatmap colors for one, two, three and four changed channels of a pixel.
+const HEAT: [[u8; 3]; 4] = [[128, 0, 0], [255, 64, 0], [255, 255, 0], [255, 255, 255]];
+
+/// Renders a single bit-plane of a channel as black and white image. Set bits are white.
+///
+/// # Arguments
+///
+/// * `img` - Image whose bit-plane will be rendered.
+/// * `channel` - Channel the bit-plane is taken from. Images without alpha channel have an opaque alpha value.
+/// * `bit` - Position of the bit, `0` for the least significant bit and `7` for the most significant bit.
+///
+pub fn bit_plane(img: &DynamicImage, channel: Channel, bit: u8) -> Result<DynamicImage, String> {
+    if bit > 7 {
+        return Err(format!("The bit-plane {bit} does not exist. Try using a bit-plane between 0 and 7."));
+    }
+
+    let rgba = img.to_rgba8();
+    let plane = GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        let value = rgba.get_pixel(x, y)[channel.index()];
+        Luma([if value.get_bit(bit as u32) { 255 } else { 0 }])
+    });
+
+    Ok(DynamicImage::ImageLuma8(plane))
+}
+
+/// Renders a heatmap of the differences between a cover image and its stego image.
+///
+/// Unchanged pixels are black, pixels with changed R, G, B or alpha values range from dark red
+/// for a single changed channel over orange and yellow to white for four changed channels.
+///
+/// # Arguments
+///
+/// * `cover` - Original image.
+/// * `stego` - Image with a hidden secret, e.g. the result of `hide_secret_img`.
+///
+pub fn lsb_heatmap(cover: &DynamicImage, stego: &DynamicImage) -> Result<DynamicImage, String> {
+    if cover.dimensions() != stego.dimensions() {
+        return Err(String::from("The images have different dimensions. Try using the cover image of the stego image."));
+    }
+
+    let (cover, stego) = (cover.to_rgba8(), stego.to_rgba8());
+    let heatmap = RgbImage::from_fn(cover.width(), cover.height(), |x, y| {
+        let (original, changed) = (cover.get_pixel(x, y), stego.get_pixel(x, y));
+        match original.0.iter().zip(changed.0).filter(|(a, b)| **a != *b).count() {
+            0 => Rgb([0, 0, 0]),
+            count => Rgb(HEAT[count - 1]),
+        }
+    });
+
+    Ok(DynamicImage::ImageRgb8(heatmap))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hips::hide_secret_img;
+    use image::Rgba;
+
+    #[test]
+    fn bit_plane_ut() {
+        let mut img = DynamicImage::new_rgba8(2, 1);
+        img.as_mut_rgba8().unwrap().put_pixel(0, 0, Rgba([0b0000_0001, 0b1000_0000, 0, 255]));
+
+        // Set bits are white
+        let plane = bit_plane(&img, Channel::Red, 0).unwrap().to_luma8();
+        assert_eq!([255, 0], [plane.get_pixel(0, 0)[0], plane.get_pixel(1, 0)[0]]);
+
+        let plane = bit_plane(&img, Channel::Green, 7).unwrap().to_luma8();
+        assert_eq!([255, 0], [plane.get_pixel(0, 0)[0], plane.get_pixel(1, 0)[0]]);
+
+        assert!(bit_plane(&img, Channel::Blue, 3).unwrap().to_luma8().pixels().all(|p| p[0] == 0));
+        assert!(bit_plane(&img, Channel::Alpha, 7).unwrap().to_luma8().pixels().any(|p| p[0] == 255));
+
+        // Bit-planes beyond the most significant bit return Error
+        assert!(bit_plane(&img, Channel::Red, 8).is_err());
+    }
+
+    #[test]
+    fn lsb_heatmap_ut() {
+        let cover = image::open("test_images/peppers.png").unwrap();
+        let secret = "Lorem ipsum dolor sit amet.";
+        let stego = hide_secret_img("test_images/peppers.png", secret, None).unwrap();
+
+        // The payload landed in the first pixels, the rest of the image is unchanged
+        let heatmap = lsb_heatmap(&cover, &stego).unwrap().to_rgb8();
+        let pixels = secret.len() * 3;
+        assert!(heatmap.pixels().take(pixels).any(|p| p.0 != [0, 0, 0]));
+        assert!(heatmap.pixels().skip(pixels).all(|p| p.0 == [0, 0, 0]));
+
+        // The heatmap of identical images is black
+        assert!(lsb_heatmap(&cover, &cover).unwrap().to_rgb8().pixels().all(|p| p.0 == [0, 0, 0]));
+
+        // Images with different dimensions return Error
+        assert!(lsb_heatmap(&cover, &DynamicImage::new_rgb8(1, 1)).is_err());
+    }
+}